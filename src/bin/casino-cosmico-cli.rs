@@ -0,0 +1,342 @@
+//! Run the raffle without Discord, against the same Redis and Tito as the bot
+use casino_cosmico::{
+    config::{Config, GuildConfig},
+    discord::commands::{
+        self,
+        eligibility::{AdminEvent, Eligibility},
+        events,
+        exclusions::{self, Exclusions},
+        export::{self, RaffleExport},
+        records::{Entry, EntrySource},
+        rehearse,
+        rng::DrawRng,
+        snapshots::{self, Trigger},
+        stats,
+    },
+    i18n::{Locale, Msg},
+    redis_pool, tito,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::json;
+use serenity::{model::id::GuildId, prelude::RwLock};
+use std::{env, error::Error, fs, io::Read};
+
+#[derive(Parser)]
+#[command(name = "casino-cosmico-cli", about = "Raffle admin without Discord")]
+struct Cli {
+    /// Guild whose raffle to use. Required when more than one is configured.
+    #[arg(long, global = true)]
+    guild: Option<u64>,
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Load checked in tickets from Tito
+    Load,
+    /// Add an entry by hand
+    Add { name: String },
+    /// Pick winners
+    Pick {
+        #[arg(long, default_value_t = 1)]
+        amount: u64,
+        /// What the winners get
+        #[arg(long)]
+        prize: Option<String>,
+    },
+    /// Draw from a copy of the raffle with a seed, nobody wins
+    Rehearse {
+        #[arg(long, default_value_t = 1)]
+        amount: usize,
+        /// Same seed, same winners. Defaults to `REHEARSAL_SEED`, then a new
+        /// one.
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Number of entries in the raffle
+    Size,
+    /// Entries by release and source, check-ins and winners
+    Stats,
+    /// Clear raffle list
+    Clear,
+    /// Dump entries and winners, as CSV or with `--format json` as JSON
+    Export,
+    /// Add entries from a CSV of names or ticket references, or merge a JSON
+    /// export. Reads stdin for `-`.
+    Import { path: String },
+}
+
+impl Command {
+    /// Whether the command changes the raffle, which archived events refuse
+    fn writes(&self) -> bool {
+        !matches!(
+            self,
+            Self::Rehearse { .. } | Self::Size | Self::Stats | Self::Export
+        )
+    }
+}
+
+fn guild_config(config: &Config, guild: Option<u64>) -> Result<&GuildConfig, String> {
+    match guild {
+        Some(guild_id) => config
+            .guild(GuildId::new(guild_id))
+            .ok_or_else(|| format!("Guild {guild_id} is not configured")),
+        None if config.guilds.len() == 1 => Ok(config.guilds.values().next().unwrap()),
+        None => Err("More than one guild is configured, pass --guild".to_string()),
+    }
+}
+
+fn print(format: Format, text: impl std::fmt::Display, json: serde_json::Value) {
+    match format {
+        Format::Text => println!("{text}"),
+        Format::Json => println!("{json}"),
+    }
+}
+
+/// Exclusions with the admin API tags of every ticket, when the rules need
+/// them and the API is set up
+async fn with_admin_tags(
+    exclusions: Exclusions,
+    admin: Option<&AdminEvent<'_>>,
+) -> Result<Exclusions, reqwest::Error> {
+    match admin {
+        Some(admin) if exclusions.needs_admin_tags() => {
+            let admin_tags =
+                exclusions::fetch_admin_tags(&admin.client, admin.account_slug, admin.event_slug)
+                    .await?;
+            Ok(exclusions.admin_tags(admin_tags))
+        }
+        _ => Ok(exclusions),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    let config = Config::from_env()?;
+    let guild = guild_config(&config, cli.guild)?;
+    let redis_url =
+        env::var("REDIS_TLS_URL").map_err(|_| "Expected env variable: REDIS_TLS_URL")?;
+    let redis_pool = redis_pool(&redis_url, 1).await?;
+    events::adopt_unscoped_keys(&redis_pool, guild).await;
+    let event = events::current(&redis_pool, guild).await;
+    if event.archived && cli.command.writes() {
+        return Err(format!("Event {} is archived, it's read-only", event.id).into());
+    }
+    let keys = event.keys(guild);
+    let tito_client = tito::checkin::client::ClientBuilder::new()?.build();
+    let admin = match (guild.tito_event(), env::var("TITO_API_TOKEN")) {
+        (Some((account_slug, event_slug)), Ok(api_token)) => Some(AdminEvent {
+            client: tito::admin::client::ClientBuilder::new(&api_token)?.build(),
+            account_slug,
+            event_slug,
+        }),
+        _ => None,
+    };
+    let exclusions = Exclusions::new(
+        guild.exclusions.clone(),
+        exclusions::excluded(&redis_pool, &keys).await,
+    );
+    let eligibility = || async {
+        Eligibility::fetch(
+            &tito_client,
+            admin.as_ref(),
+            &redis_pool,
+            &keys,
+            &guild.checkin_list_slug,
+            guild.rules(),
+        )
+        .await
+        .map(|eligibility| eligibility.with_win_cap(guild.win_cap))
+    };
+
+    match cli.command {
+        Command::Load => {
+            let exclusions = with_admin_tags(exclusions, admin.as_ref()).await?;
+            let params = commands::LoadParams {
+                checkin_list_slug: &guild.checkin_list_slug,
+                keys: &keys,
+                rules: guild.rules(),
+                admin: admin.clone(),
+                exclusions,
+            };
+            let report =
+                commands::load_names(&tito_client, &redis_pool, params, |progress| async move {
+                    eprintln!("{progress}");
+                })
+                .await?;
+            let excluded: serde_json::Map<String, serde_json::Value> = report
+                .excluded
+                .iter()
+                .map(|(reason, count)| (reason.text(Locale::En), json!(count)))
+                .collect();
+            print(
+                cli.format,
+                &report,
+                json!({ "loaded": report.loaded, "total": report.total, "excluded": excluded }),
+            );
+        }
+        Command::Add { name } => {
+            let eligibility = eligibility().await?;
+            let entry = Entry::new(&name, EntrySource::Manual);
+            if let Some(reason) = exclusions.entry(&entry, eligibility.tickets()) {
+                let reason = reason.text(Locale::En);
+                print(
+                    cli.format,
+                    format!("{name} is excluded from the raffle ({reason})"),
+                    json!({ "name": name, "added": false, "excluded": reason }),
+                );
+                return Ok(());
+            }
+            if let Some(rule) = eligibility.entry(&entry) {
+                let reason = Msg::BrokenRule(rule).text(Locale::En);
+                print(
+                    cli.format,
+                    format!("{name} can't enter the raffle: {reason}"),
+                    json!({ "name": name, "added": false, "ineligible": reason }),
+                );
+                return Ok(());
+            }
+
+            let added = commands::add_name(&redis_pool, &keys, &name).await?;
+            let text = if added {
+                format!("Added {name}")
+            } else {
+                format!("{name} is already in the raffle")
+            };
+            print(cli.format, text, json!({ "name": name, "added": added }));
+        }
+        Command::Pick { amount, prize } => {
+            let rng = RwLock::new(DrawRng::os());
+            let eligibility = eligibility().await?;
+            let winners = commands::pick_winners(
+                &redis_pool,
+                &keys,
+                &rng,
+                &eligibility,
+                prize.as_deref(),
+                amount as usize,
+            )
+            .await;
+
+            let mut lines: Vec<String> = winners
+                .iter()
+                .map(|winner| format!("Winner #{}: {}", winner.number, winner.name))
+                .collect();
+            if winners.is_empty() {
+                lines.push("No entries in the raffle.".to_string());
+            } else if (winners.len() as u64) < amount {
+                let drawn = winners.len();
+                lines.push(Msg::FewerWinners { drawn, amount }.text(Locale::En));
+            }
+            print(
+                cli.format,
+                lines.join("\n"),
+                json!({ "requested": amount, "winners": winners }),
+            );
+        }
+        Command::Rehearse { amount, seed } => {
+            let eligibility = eligibility().await?;
+            let entries = commands::records::entries(&redis_pool, &keys).await?;
+            let wins = commands::win_cap::wins(&redis_pool, &keys).await?;
+            let seed = seed
+                .or(guild.rehearsal_seed)
+                .unwrap_or_else(DrawRng::new_seed);
+            let winners = rehearse::rehearse(
+                entries,
+                wins,
+                &eligibility,
+                amount,
+                &mut DrawRng::seeded(seed),
+            );
+
+            let names: Vec<&str> = winners.iter().map(|winner| winner.name.as_str()).collect();
+            let text = if names.is_empty() {
+                "No entries in the raffle.".to_string()
+            } else {
+                Msg::Rehearsal {
+                    seed,
+                    winners: &names.join("\n"),
+                }
+                .text(Locale::En)
+            };
+            print(cli.format, text, json!({ "seed": seed, "winners": names }));
+        }
+        Command::Size => {
+            let size = commands::raffle_size(&redis_pool, &keys).await?;
+            print(
+                cli.format,
+                format!("{size} entries in the raffle"),
+                json!({ "size": size }),
+            );
+        }
+        Command::Stats => {
+            let stats =
+                stats::stats(&tito_client, &redis_pool, &keys, &guild.checkin_list_slug).await?;
+            let text = stats
+                .fields(guild.time_zone, Locale::En)
+                .into_iter()
+                .map(|(name, value)| format!("{name}\n{}", value.trim_matches('`').trim()))
+                .collect::<Vec<String>>()
+                .join("\n\n");
+            print(cli.format, text, json!(stats));
+        }
+        Command::Clear => {
+            let snapshot =
+                snapshots::take(&guild.snapshots, &redis_pool, &keys, Trigger::Clear).await?;
+            eprintln!("Saved snapshot #{}", snapshot.id);
+            commands::clear_names(&redis_pool, &keys).await?;
+            print(cli.format, "Cleared list", json!({ "cleared": true }));
+        }
+        Command::Export => {
+            let export = export::export_raffle(&redis_pool, &keys).await?;
+            match cli.format {
+                Format::Text => print!("{}", export.to_csv()?),
+                Format::Json => println!("{}", export.to_json()?),
+            }
+        }
+        Command::Import { path } => {
+            let mut contents = String::new();
+            if path == "-" {
+                std::io::stdin().read_to_string(&mut contents)?;
+            } else {
+                contents = fs::read_to_string(&path)?;
+            }
+
+            let exclusions = with_admin_tags(exclusions, admin.as_ref()).await?;
+            let eligibility = eligibility().await?;
+            let snapshot =
+                snapshots::take(&guild.snapshots, &redis_pool, &keys, Trigger::Import).await?;
+            eprintln!("Saved snapshot #{}", snapshot.id);
+            let report = if contents.trim_start().starts_with('{') {
+                let export: RaffleExport = serde_json::from_str(&contents)?;
+                export::import_export(&redis_pool, &keys, &exclusions, &eligibility, export).await?
+            } else {
+                let rows = export::parse_csv(contents.as_bytes())?;
+                export::import_rows(&redis_pool, &keys, &exclusions, &eligibility, rows).await?
+            };
+            print(
+                cli.format,
+                report.text(Locale::En),
+                json!({
+                    "imported": report.added,
+                    "duplicates": report.duplicates,
+                    "unknown_references": report.unknown_references,
+                    "excluded": report.excluded,
+                    "ineligible": report.ineligible,
+                }),
+            );
+        }
+    }
+
+    Ok(())
+}
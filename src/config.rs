@@ -0,0 +1,437 @@
+//! Bot configuration, loaded from the environment
+use crate::{
+    eligibility::{Rule, Rules},
+    i18n::Locale,
+};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use serenity::model::id::{ChannelId, GuildId, RoleId};
+use std::{collections::HashMap, env, path::PathBuf, time::Duration};
+
+const EARLY_BIRD_TICKET_SLUG: &str = "Con of Heroes 2024 Early Bird Ticket";
+const GENERAL_TICKET_SLUG: &str = "Con of heroes 2024 General Ticket";
+const NO_SWAG_TICKET_SLUG: &str = "No-SWAG ticket";
+const TICKET_SPOOFER_SLUG: &str = "Ticket spoofer";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("Expected env variable: {0}")]
+    MissingVar(&'static str),
+    #[error("{0} must be an integer")]
+    InvalidId(&'static str),
+    #[error("Invalid GUILD_CONFIG: {0}")]
+    InvalidGuildConfig(#[from] serde_json::Error),
+    #[error("Invalid COMMAND_SCOPE: {0}, expected `guild` or `global`")]
+    InvalidCommandScope(String),
+    #[error("Guild {0} is configured more than once")]
+    DuplicateGuild(GuildId),
+}
+
+/// Where slash commands get registered on `ready`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommandScope {
+    /// Register commands on each configured guild. Updates are instant.
+    #[default]
+    Guild,
+    /// Register commands once for the whole application.
+    Global,
+}
+
+/// Settings for a single Discord server
+#[derive(Clone, Debug, Deserialize)]
+pub struct GuildConfig {
+    pub guild_id: GuildId,
+    /// Channel winners get announced in and reactions are watched on
+    pub channel_id: ChannelId,
+    pub checkin_list_slug: String,
+    /// Prefix for every Redis key of this guild. Defaults to the guild id.
+    #[serde(default)]
+    pub redis_namespace: Option<String>,
+    /// Release titles eligible for the raffle
+    #[serde(default = "default_release_titles")]
+    pub release_titles: Vec<String>,
+    /// Roles allowed to run the raffle. Anyone may when empty.
+    #[serde(default)]
+    pub organizer_role_ids: Vec<RoleId>,
+    /// Tito account for admin API lookups, such as winner avatars
+    #[serde(default)]
+    pub tito_account_slug: Option<String>,
+    /// Tito event for admin API lookups
+    #[serde(default)]
+    pub tito_event_slug: Option<String>,
+    #[serde(default)]
+    pub draw_animation: DrawAnimation,
+    /// Seconds a winner has to claim before another is drawn. No timer when 0,
+    /// the default.
+    #[serde(default)]
+    pub claim_timeout_secs: u64,
+    /// Direct message sent to winners with a linked Discord account.
+    /// `{name}`, `{prize}`, `{release}` and `{draw}` are filled in.
+    #[serde(default)]
+    pub winner_dm_template: Option<String>,
+    /// Re-add a winner when anyone reacts 👍 to their announcement, from
+    /// before "Return to raffle" existed
+    #[serde(default)]
+    pub legacy_reaction_readd: bool,
+    /// Language of bot responses. Follows each member's Discord language when
+    /// unset.
+    #[serde(default)]
+    pub locale: Option<Locale>,
+    /// Time zone of the event, for `/raffle schedule` times
+    #[serde(default)]
+    pub time_zone: Tz,
+    #[serde(default)]
+    pub exclusions: ExclusionRules,
+    /// Rules every entry has to pass. Defaults to a checked in, named ticket
+    /// of one of the `release_titles`.
+    #[serde(default)]
+    pub eligibility: Option<Vec<Rule>>,
+    /// Event the raffle starts in. Defaults to the Tito event slug, then the
+    /// check-in list.
+    #[serde(default)]
+    pub event_id: Option<String>,
+    /// Times one ticket may win across the raffles of the event. No cap when
+    /// unset.
+    #[serde(default)]
+    pub win_cap: Option<u32>,
+    #[serde(default)]
+    pub snapshots: SnapshotConfig,
+    /// Days an archived event is kept, read-only. Kept for good when 0.
+    #[serde(default = "default_archive_ttl_days")]
+    pub archive_ttl_days: u64,
+    /// Seed `/raffle rehearse` uses when it isn't given one
+    #[serde(default)]
+    pub rehearsal_seed: Option<u64>,
+}
+
+/// Tickets kept out of the raffle, such as staff and guests. More can be
+/// added with `/raffle exclude`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ExclusionRules {
+    /// Tito tags, from the check-in list or the admin API
+    pub tags: Vec<String>,
+    /// Email domains, subdomains included
+    pub email_domains: Vec<String>,
+    pub ticket_references: Vec<String>,
+}
+
+/// Where raffle snapshots are kept
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotStore {
+    #[default]
+    Redis,
+    /// JSON files under `directory`. Only for hosts with a persistent disk,
+    /// Heroku wipes a dyno's filesystem on every restart.
+    Disk,
+}
+
+/// Copies of the raffle taken before destructive commands and on a timer
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    pub store: SnapshotStore,
+    /// Directory of the disk store
+    pub directory: PathBuf,
+    /// Minutes between timed snapshots, which are skipped when nothing
+    /// changed. None when 0.
+    pub interval_minutes: u64,
+    /// Snapshots kept, the oldest are deleted
+    pub keep: usize,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            store: SnapshotStore::Redis,
+            directory: PathBuf::from("snapshots"),
+            interval_minutes: 15,
+            keep: 48,
+        }
+    }
+}
+
+/// Pacing of `/raffle pick dramatic:True`
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct DrawAnimation {
+    /// Names flashed before the winner
+    pub frames: usize,
+    /// Pause after the first name, in milliseconds
+    pub start_delay_ms: u64,
+    /// Pause before the winner, in milliseconds. The pauses in between grow
+    /// from the start delay to this.
+    pub end_delay_ms: u64,
+}
+
+impl Default for DrawAnimation {
+    fn default() -> Self {
+        Self {
+            frames: 12,
+            start_delay_ms: 400,
+            end_delay_ms: 2000,
+        }
+    }
+}
+
+impl DrawAnimation {
+    /// Pause after each frame, slowing down towards the reveal
+    pub fn delays(&self) -> Vec<Duration> {
+        let start = self.start_delay_ms as f64;
+        let end = self.end_delay_ms as f64;
+        let steps = self.frames.saturating_sub(1).max(1) as f64;
+
+        (0..self.frames)
+            .map(|frame| {
+                let delay = start + (end - start) * (frame as f64 / steps).powi(2);
+                Duration::from_millis(delay.round() as u64)
+            })
+            .collect()
+    }
+}
+
+fn default_archive_ttl_days() -> u64 {
+    90
+}
+
+fn default_release_titles() -> Vec<String> {
+    [
+        EARLY_BIRD_TICKET_SLUG,
+        GENERAL_TICKET_SLUG,
+        NO_SWAG_TICKET_SLUG,
+        TICKET_SPOOFER_SLUG,
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+impl GuildConfig {
+    /// Namespaced Redis key for this guild
+    pub fn redis_key(&self, key: &str) -> String {
+        match self.redis_namespace.as_deref() {
+            Some("") => key.to_string(),
+            Some(namespace) => format!("{namespace}:{key}"),
+            None => format!("{}:{key}", self.guild_id),
+        }
+    }
+
+    /// Id of the event raffles run in until `/event start` picks another
+    pub fn event(&self) -> &str {
+        self.event_id
+            .as_deref()
+            .or(self.tito_event_slug.as_deref())
+            .unwrap_or(&self.checkin_list_slug)
+    }
+
+    /// Account and event slugs, if admin API lookups are configured
+    pub fn tito_event(&self) -> Option<(&str, &str)> {
+        Some((
+            self.tito_account_slug.as_deref()?,
+            self.tito_event_slug.as_deref()?,
+        ))
+    }
+
+    pub fn rules(&self) -> Rules {
+        Rules::new(self.eligibility.clone().unwrap_or_else(|| {
+            vec![
+                Rule::ReleaseIn {
+                    titles: self.release_titles.clone(),
+                },
+                Rule::CheckedIn {
+                    after: None,
+                    before: None,
+                },
+                Rule::Named,
+            ]
+        }))
+    }
+
+    /// Whether a member with these roles may run organizer commands
+    pub fn is_organizer(&self, roles: &[RoleId]) -> bool {
+        self.organizer_role_ids.is_empty()
+            || roles
+                .iter()
+                .any(|role| self.organizer_role_ids.contains(role))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub command_scope: CommandScope,
+    pub guilds: HashMap<GuildId, GuildConfig>,
+}
+
+impl Config {
+    /// Read guilds from `GUILD_CONFIG`, a JSON list of [`GuildConfig`]. Falls
+    /// back to a single guild built from `DISCORD_GUILD_ID`,
+    /// `DISCORD_CHANNEL_ID` and `CHECKIN_LIST_SLUG` using the un-namespaced keys.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let command_scope = match env::var("COMMAND_SCOPE").ok().as_deref() {
+            None | Some("guild") => CommandScope::Guild,
+            Some("global") => CommandScope::Global,
+            Some(other) => return Err(ConfigError::InvalidCommandScope(other.to_string())),
+        };
+
+        let guilds = match env::var("GUILD_CONFIG") {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(_) => vec![legacy_guild_config()?],
+        };
+
+        Self::new(command_scope, guilds)
+    }
+
+    pub fn new(command_scope: CommandScope, guilds: Vec<GuildConfig>) -> Result<Self, ConfigError> {
+        let mut map = HashMap::with_capacity(guilds.len());
+        for guild in guilds {
+            let guild_id = guild.guild_id;
+            if map.insert(guild_id, guild).is_some() {
+                return Err(ConfigError::DuplicateGuild(guild_id));
+            }
+        }
+
+        Ok(Self {
+            command_scope,
+            guilds: map,
+        })
+    }
+
+    pub fn guild(&self, guild_id: GuildId) -> Option<&GuildConfig> {
+        self.guilds.get(&guild_id)
+    }
+}
+
+fn legacy_guild_config() -> Result<GuildConfig, ConfigError> {
+    let var = |name: &'static str| env::var(name).map_err(|_| ConfigError::MissingVar(name));
+    let id = |name: &'static str| -> Result<u64, ConfigError> {
+        var(name)?.parse().map_err(|_| ConfigError::InvalidId(name))
+    };
+    let list = |name: &'static str| -> Vec<String> {
+        env::var(name)
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    Ok(GuildConfig {
+        guild_id: GuildId::new(id("DISCORD_GUILD_ID")?),
+        channel_id: ChannelId::new(id("DISCORD_CHANNEL_ID")?),
+        checkin_list_slug: var("CHECKIN_LIST_SLUG")?,
+        redis_namespace: Some(String::new()),
+        release_titles: default_release_titles(),
+        organizer_role_ids: Vec::new(),
+        tito_account_slug: env::var("TITO_ACCOUNT_SLUG").ok(),
+        tito_event_slug: env::var("TITO_EVENT_SLUG").ok(),
+        draw_animation: DrawAnimation::default(),
+        claim_timeout_secs: env::var("CLAIM_TIMEOUT_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or_default(),
+        winner_dm_template: env::var("WINNER_DM_TEMPLATE").ok(),
+        legacy_reaction_readd: env::var("LEGACY_REACTION_READD").is_ok_and(|value| value == "true"),
+        locale: env::var("LOCALE")
+            .ok()
+            .and_then(|locale| Locale::from_discord(&locale)),
+        time_zone: env::var("TIME_ZONE")
+            .ok()
+            .and_then(|time_zone| time_zone.parse().ok())
+            .unwrap_or_default(),
+        exclusions: ExclusionRules {
+            tags: list("EXCLUDED_TAGS"),
+            email_domains: list("EXCLUDED_EMAIL_DOMAINS"),
+            ticket_references: list("EXCLUDED_TICKETS"),
+        },
+        eligibility: None,
+        event_id: env::var("EVENT_ID").ok(),
+        win_cap: env::var("WIN_CAP").ok().and_then(|cap| cap.parse().ok()),
+        snapshots: {
+            let defaults = SnapshotConfig::default();
+            SnapshotConfig {
+                store: match env::var("SNAPSHOT_STORE").ok().as_deref() {
+                    Some("disk") => SnapshotStore::Disk,
+                    _ => SnapshotStore::Redis,
+                },
+                directory: env::var("SNAPSHOT_DIR")
+                    .map(PathBuf::from)
+                    .unwrap_or(defaults.directory),
+                interval_minutes: env::var("SNAPSHOT_INTERVAL_MINUTES")
+                    .ok()
+                    .and_then(|minutes| minutes.parse().ok())
+                    .unwrap_or(defaults.interval_minutes),
+                keep: env::var("SNAPSHOT_KEEP")
+                    .ok()
+                    .and_then(|keep| keep.parse().ok())
+                    .unwrap_or(defaults.keep),
+            }
+        },
+        archive_ttl_days: env::var("ARCHIVE_TTL_DAYS")
+            .ok()
+            .and_then(|days| days.parse().ok())
+            .unwrap_or_else(default_archive_ttl_days),
+        rehearsal_seed: env::var("REHEARSAL_SEED")
+            .ok()
+            .and_then(|seed| seed.parse().ok()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guild_config_deserializes() {
+        let guilds: Vec<GuildConfig> = serde_json::from_str(
+            r#"[
+                {"guild_id": "1", "channel_id": 2, "checkin_list_slug": "chk_a"},
+                {"guild_id": "3", "channel_id": "4", "checkin_list_slug": "chk_b",
+                 "redis_namespace": "cosmico", "organizer_role_ids": ["5"]}
+            ]"#,
+        )
+        .unwrap();
+        let config = Config::new(CommandScope::Guild, guilds).unwrap();
+
+        let first = config.guild(GuildId::new(1)).unwrap();
+        assert_eq!(first.redis_key("raffle"), "1:raffle");
+        assert_eq!(first.release_titles, default_release_titles());
+        assert_eq!(first.claim_timeout_secs, 0);
+        assert!(first.is_organizer(&[]));
+
+        let second = config.guild(GuildId::new(3)).unwrap();
+        assert_eq!(second.redis_key("raffle"), "cosmico:raffle");
+        assert!(second.is_organizer(&[RoleId::new(5)]));
+        assert!(!second.is_organizer(&[RoleId::new(6)]));
+    }
+
+    #[test]
+    fn empty_namespace_keeps_legacy_keys() {
+        let guild: GuildConfig = serde_json::from_str(
+            r#"{"guild_id": 1, "channel_id": 2, "checkin_list_slug": "chk", "redis_namespace": ""}"#,
+        )
+        .unwrap();
+
+        assert_eq!(guild.redis_key("loaded"), "loaded");
+        assert_eq!(guild.event(), "chk");
+    }
+
+    #[test]
+    fn draw_animation_slows_down() {
+        let animation = DrawAnimation {
+            frames: 5,
+            start_delay_ms: 100,
+            end_delay_ms: 900,
+        };
+        let delays = animation.delays();
+
+        assert_eq!(delays.len(), 5);
+        assert_eq!(delays[0], Duration::from_millis(100));
+        assert_eq!(delays[4], Duration::from_millis(900));
+        assert!(delays.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
@@ -1,20 +1,254 @@
+pub mod animation;
+pub mod announce;
+pub mod autocomplete;
+pub mod claims;
+pub mod draw;
+pub mod eligibility;
+pub mod enter;
+pub mod entries;
+pub mod events;
+pub mod exclusions;
+pub mod export;
+pub mod history;
+pub mod readd;
+pub mod records;
+pub mod rehearse;
+pub mod rng;
+pub mod schedule;
+pub mod snapshots;
+pub mod stats;
+pub mod win_cap;
+
+use crate::config::GuildConfig;
 use crate::discord::type_map_keys;
+use crate::eligibility::Rules;
+use crate::i18n::{Locale, Msg};
+use crate::metrics;
 use crate::tito::checkin::client::Client;
-use bb8_redis::redis::AsyncCommands;
+use bb8_redis::redis::{self, AsyncCommands};
+use eligibility::{AdminEvent, Eligibility};
+use exclusions::{ExclusionReport, Exclusions};
+use records::{Entry, EntrySource, Winner};
+use rng::DrawRng;
 use serenity::{
-    builder::{CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage},
+    builder::{
+        CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+        EditInteractionResponse, EditMessage,
+    },
     client::Context,
-    model::application::CommandInteraction,
+    model::{application::CommandInteraction, id::ChannelId},
+    prelude::RwLock,
 };
-use std::collections::HashSet;
-use tracing::instrument;
+use snapshots::Trigger;
+use std::collections::HashMap;
+use tracing::{error, info, instrument};
+
+const LOADED_REDIS_KEY: &str = "loaded";
+const RAFFLE_REDIS_KEY: &str = "raffle";
+const ENTRIES_REDIS_KEY: &str = "entries";
+const WINNERS_REDIS_KEY: &str = "winners";
+const DRAW_COUNT_REDIS_KEY: &str = "draw_count";
+const CLAIMS_REDIS_KEY: &str = "claims";
+const CLAIM_DEADLINES_REDIS_KEY: &str = "claim_deadlines";
+const TICKET_USERS_REDIS_KEY: &str = "ticket_users";
+const USER_TICKETS_REDIS_KEY: &str = "user_tickets";
+const SCHEDULES_REDIS_KEY: &str = "schedules";
+const SCHEDULE_COUNT_REDIS_KEY: &str = "schedule_count";
+const EXCLUDED_REDIS_KEY: &str = "excluded";
+const SKIPPED_REDIS_KEY: &str = "skipped";
+const WINS_REDIS_KEY: &str = "wins";
+const SNAPSHOTS_REDIS_KEY: &str = "snapshots";
+const SNAPSHOT_COUNT_REDIS_KEY: &str = "snapshot_count";
+const ARCHIVED_REDIS_KEY: &str = "archived";
+
+/// Redis keys holding one guild's raffle for one event
+#[derive(Clone, Debug)]
+pub struct RedisKeys {
+    /// Set of every name ever added, so loading again skips past winners
+    pub loaded: String,
+    /// List of names left in the raffle
+    pub raffle: String,
+    /// Hash of name to [`Entry`]
+    pub entries: String,
+    /// List of [`Winner`] in draw order
+    pub winners: String,
+    /// Number of the last draw
+    pub draw_count: String,
+    /// Hash of draw number to the announcement of a winner yet to claim
+    pub claims: String,
+    /// Sorted set of draw numbers by claim deadline
+    pub claim_deadlines: String,
+    /// Hash of ticket reference to the Discord user linked to it. Kept when
+    /// the raffle is cleared.
+    pub ticket_users: String,
+    /// Hash of Discord user to their linked ticket reference
+    pub user_tickets: String,
+    /// Hash of id to [`schedule::Schedule`]. Kept when the raffle is cleared.
+    pub schedules: String,
+    /// Id of the last schedule
+    pub schedule_count: String,
+    /// Set of lowercase ticket references, names and emails kept out of the
+    /// raffle. Kept when the raffle is cleared.
+    pub excluded: String,
+    /// List of [`records::Skip`] in draw order
+    pub skipped: String,
+    /// Hash of ticket reference to wins across the raffles of the event
+    pub wins: String,
+    /// Hash of id to [`snapshots::Snapshot`], or the directory of the disk
+    /// store. Kept when the raffle is cleared.
+    pub snapshots: String,
+    /// Id of the last snapshot in Redis
+    pub snapshot_count: String,
+    /// When the event was archived. Expires with the rest of its keys.
+    pub archived: String,
+}
+
+impl RedisKeys {
+    /// Keys of the guild's raffle for one event
+    pub fn new(guild: &GuildConfig, event: &str) -> Self {
+        Self::build(|key| guild.redis_key(&format!("{event}:{key}")))
+    }
+
+    /// Keys from before raffles were kept per event
+    pub fn unscoped(guild: &GuildConfig) -> Self {
+        Self::build(|key| guild.redis_key(key))
+    }
+
+    fn build(key: impl Fn(&str) -> String) -> Self {
+        Self {
+            loaded: key(LOADED_REDIS_KEY),
+            raffle: key(RAFFLE_REDIS_KEY),
+            entries: key(ENTRIES_REDIS_KEY),
+            winners: key(WINNERS_REDIS_KEY),
+            draw_count: key(DRAW_COUNT_REDIS_KEY),
+            claims: key(CLAIMS_REDIS_KEY),
+            claim_deadlines: key(CLAIM_DEADLINES_REDIS_KEY),
+            ticket_users: key(TICKET_USERS_REDIS_KEY),
+            user_tickets: key(USER_TICKETS_REDIS_KEY),
+            schedules: key(SCHEDULES_REDIS_KEY),
+            schedule_count: key(SCHEDULE_COUNT_REDIS_KEY),
+            excluded: key(EXCLUDED_REDIS_KEY),
+            skipped: key(SKIPPED_REDIS_KEY),
+            wins: key(WINS_REDIS_KEY),
+            snapshots: key(SNAPSHOTS_REDIS_KEY),
+            snapshot_count: key(SNAPSHOT_COUNT_REDIS_KEY),
+            archived: key(ARCHIVED_REDIS_KEY),
+        }
+    }
+
+    /// Every key, in the same order for any guild and event
+    pub fn all(&self) -> [&str; 17] {
+        [
+            &self.loaded,
+            &self.raffle,
+            &self.entries,
+            &self.winners,
+            &self.draw_count,
+            &self.claims,
+            &self.claim_deadlines,
+            &self.ticket_users,
+            &self.user_tickets,
+            &self.schedules,
+            &self.schedule_count,
+            &self.excluded,
+            &self.skipped,
+            &self.wins,
+            &self.snapshots,
+            &self.snapshot_count,
+            &self.archived,
+        ]
+    }
+}
 
 #[derive(Debug)]
 pub struct LoadParams<'a> {
     pub checkin_list_slug: &'a str,
-    pub loaded_redis_key: &'a str,
-    pub raffle_redis_key: &'a str,
-    pub ticket_slugs: Vec<String>,
+    pub keys: &'a RedisKeys,
+    pub rules: Rules,
+    /// For rules that need tags or answers from the admin API
+    pub admin: Option<AdminEvent<'a>>,
+    pub exclusions: Exclusions,
+}
+
+/// What `load_names` did
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    /// Entries added
+    pub loaded: usize,
+    /// Size of the raffle afterwards
+    pub total: usize,
+    pub excluded: ExclusionReport,
+}
+
+impl LoadReport {
+    pub fn text(&self, locale: Locale) -> String {
+        let mut text = Msg::Loaded {
+            loaded: &thousands(self.loaded),
+            total: &thousands(self.total),
+        }
+        .text(locale);
+        if !self.excluded.is_empty() {
+            text.push('\n');
+            text.push_str(
+                &Msg::Excluded {
+                    count: self.excluded.values().sum(),
+                    reasons: &exclusions::report_text(&self.excluded, locale),
+                }
+                .text(locale),
+            );
+        }
+
+        text
+    }
+}
+
+impl std::fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text(Locale::En))
+    }
+}
+
+/// How far along `load_names` is
+#[derive(Clone, Copy, Debug)]
+pub enum LoadProgress {
+    Fetched { tickets: usize },
+    Filtered { tickets: usize, attendees: usize },
+}
+
+impl LoadProgress {
+    pub fn text(&self, locale: Locale) -> String {
+        match self {
+            Self::Fetched { tickets } => Msg::LoadFetched {
+                tickets: &thousands(*tickets),
+            }
+            .text(locale),
+            Self::Filtered { tickets, attendees } => Msg::LoadFiltered {
+                tickets: &thousands(*tickets),
+                attendees: &thousands(*attendees),
+            }
+            .text(locale),
+        }
+    }
+}
+
+impl std::fmt::Display for LoadProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text(Locale::En))
+    }
+}
+
+/// Group digits by thousands, `1200` as `1,200`
+pub fn thousands(number: usize) -> String {
+    let digits = number.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    grouped
 }
 
 #[instrument(skip(ctx))]
@@ -22,258 +256,739 @@ pub async fn load<'a>(
     ctx: &Context,
     command: &CommandInteraction,
     params: LoadParams<'a>,
+    locale: Locale,
 ) -> serenity::Result<()> {
+    // a large check-in list takes longer than the interaction deadline
+    command.defer(&ctx.http).await?;
+
     let tito_client = type_map_keys::TitoClient::get(&ctx.data).await;
     let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
 
-    let (loaded, total) = load_names(&tito_client, &redis_pool, params).await?;
+    let report = load_names(&tito_client, &redis_pool, params, |progress| async move {
+        if let Err(err) = command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new().content(progress.text(locale)),
+            )
+            .await
+        {
+            error!("Cannot show load progress: {}", err);
+        }
+    })
+    .await?;
 
     command
-        .create_response(
+        .edit_response(
             &ctx.http,
-            CreateInteractionResponse::Message(
-                CreateInteractionResponseMessage::new()
-                    .content(format!("Loaded {loaded} users\n{total} total users.",)),
-            ),
+            EditInteractionResponse::new().content(report.text(locale)),
         )
         .await
+        .map(|_| ())
 }
 
-async fn load_names<'a>(
+/// Load checked in attendees from Tito into the raffle, skipping excluded
+/// tickets. `progress` is told about each step along the way.
+pub async fn load_names<'a, F, Fut>(
     tito_client: &Client,
     redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
     params: LoadParams<'a>,
-) -> serenity::Result<(usize, usize)> {
-    let mut redis_connection = redis_pool.get().await.unwrap();
-
-    let (checkins, tickets) = futures::future::try_join(
-        tito_client
-            .check_ins(params.checkin_list_slug)
-            .checkins()
-            .send(),
-        tito_client
-            .check_ins(params.checkin_list_slug)
-            .tickets()
-            .send(),
+    mut progress: F,
+) -> serenity::Result<LoadReport>
+where
+    F: FnMut(LoadProgress) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let eligibility = Eligibility::fetch(
+        tito_client,
+        params.admin.as_ref(),
+        redis_pool,
+        params.keys,
+        params.checkin_list_slug,
+        params.rules,
     )
     .await
     .unwrap();
-    let checkins_hash: HashSet<u32> =
-        HashSet::from_iter(checkins.iter().map(|checkin| checkin.ticket_id));
+    let tickets = eligibility.tickets();
+    progress(LoadProgress::Fetched {
+        tickets: tickets.len(),
+    })
+    .await;
 
-    let already_loaded: Vec<String> = redis_connection
-        .smembers(params.loaded_redis_key)
-        .await
-        .unwrap();
+    // fetching the rules and saving records take a connection of their own,
+    // and the pool may only have the one
+    let already_loaded: Vec<String> = {
+        let mut redis_connection = redis_pool.get().await.unwrap();
+        redis_connection
+            .smembers(&params.keys.loaded)
+            .await
+            .unwrap()
+    };
+    let mut excluded = ExclusionReport::new();
     let attendees = tickets
         .iter()
         .filter_map(|ticket| {
-            if params.ticket_slugs.contains(&ticket.release_title)
-                && checkins_hash.get(&ticket.id).is_some()
-            {
-                if let Some(first_name) = &ticket.first_name {
-                    if let Some(last_name) = &ticket.last_name {
-                        let name = format!("{first_name} {last_name}");
-                        if !already_loaded.contains(&name) {
-                            return Some(name);
-                        }
-                    }
-                }
+            // entries go by name, so a ticket without one can't enter
+            let name = ticket.full_name()?;
+            if already_loaded.contains(&name) || eligibility.ticket(ticket).is_some() {
+                return None;
+            }
+            if let Some(reason) = params.exclusions.ticket(ticket) {
+                *excluded.entry(reason).or_default() += 1;
+                return None;
             }
 
-            None
+            let entry = Entry::new(&name, EntrySource::Tito)
+                .ticket_reference(&ticket.reference)
+                .release_title(&ticket.release_title);
+            Some((name, entry))
         })
-        .collect::<Vec<String>>();
-    let unique_attendees = HashSet::<String>::from_iter(attendees);
+        .collect::<Vec<(String, Entry)>>();
+    let unique_attendees = HashMap::<String, Entry>::from_iter(attendees);
+    let added = unique_attendees.len();
+    progress(LoadProgress::Filtered {
+        tickets: tickets.len(),
+        attendees: added,
+    })
+    .await;
     // this will error with an empty set
     if !unique_attendees.is_empty() {
+        let names = unique_attendees.keys().collect::<Vec<&String>>();
+        let mut redis_connection = redis_pool.get().await.unwrap();
         let _: () = redis_connection
-            .rpush(params.raffle_redis_key, &unique_attendees)
+            .rpush(&params.keys.raffle, &names)
             .await
             .unwrap();
         let _: () = redis_connection
-            .sadd(params.loaded_redis_key, &unique_attendees)
+            .sadd(&params.keys.loaded, &names)
+            .await
+            .unwrap();
+        drop(redis_connection);
+        let entries = unique_attendees.into_values().collect::<Vec<Entry>>();
+        records::save_entries(redis_pool, params.keys, &entries)
             .await
             .unwrap();
     }
-    let loaded: Vec<String> = redis_connection
-        .lrange(params.raffle_redis_key, 0, -1)
-        .await
-        .unwrap();
 
-    Ok((unique_attendees.len(), loaded.len()))
+    Ok(LoadReport {
+        loaded: added,
+        total: raffle_size(redis_pool, params.keys).await?,
+        excluded,
+    })
+}
+
+/// What `/raffle pick` was asked to draw
+#[derive(Clone, Copy, Debug)]
+pub struct Draw<'a> {
+    pub amount: u64,
+    pub prize: Option<&'a str>,
+    /// Play the animation once, landing on the first winner, then reveal the
+    /// whole batch
+    pub dramatic: bool,
 }
 
 #[instrument(skip(ctx))]
 pub async fn raffle(
     ctx: &Context,
     command: &CommandInteraction,
-    redis_key: &str,
-    amount: u64,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    draw: Draw<'_>,
 ) -> serenity::Result<()> {
     let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let rng = type_map_keys::Rng::get(&ctx.data).await;
+    let shutdown = type_map_keys::Shutdown::get(&ctx.data).await;
+    let locale = Locale::resolve(guild.locale, &command.locale);
+    // re-checking the rules asks Tito, which can outlast the interaction deadline
+    command.defer(&ctx.http).await?;
+    let eligibility = eligibility::for_draw(ctx, guild, keys).await;
+    // names to flash during the animation, taken before anyone is drawn
+    let names = animation_names(&redis_pool, keys, draw.dramatic).await;
+
+    let winners = pick_winners(
+        &redis_pool,
+        keys,
+        &rng,
+        &eligibility,
+        draw.prize,
+        draw.amount as usize,
+    )
+    .await;
+    let Some(winner) = winners.first() else {
+        return command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new().content(Msg::NoEntries.text(locale)),
+            )
+            .await
+            .map(|_| ());
+    };
+    draws(guild).inc_by(winners.len() as u64);
+
+    if draw.dramatic {
+        command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new().content(Msg::Drawing.text(locale)),
+            )
+            .await?;
+        let decoys = {
+            let mut rng = rng.write().await;
+            animation::decoys(&names, &winner.name, guild.draw_animation.frames, &mut *rng)
+        };
+        animation::play(&guild.draw_animation, decoys, &shutdown, |content| {
+            command.edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+        })
+        .await?;
+    }
+
+    if draw.amount > 1 {
+        let (embed, components) = announce::summary(&winners, 0, locale);
+        let mut mentions = announce::mentions(&redis_pool, keys, &winners)
+            .await
+            .into_iter();
+        let content = summary_content(mentions.next(), winners.len(), draw.amount, locale);
+        let message = command
+            .edit_response(
+                &ctx.http,
+                EditInteractionResponse::new()
+                    .content(content)
+                    .embed(embed)
+                    .components(components),
+            )
+            .await?;
+        for mentions in mentions {
+            command
+                .channel_id
+                .send_message(&ctx.http, CreateMessage::new().content(mentions))
+                .await?;
+        }
+        for winner in &winners {
+            announce::follow_up(ctx, guild, keys, winner, &message, locale).await?;
+        }
+        return Ok(());
+    }
+
+    let avatar_url = announce::avatar_url(ctx, guild, winner).await;
+    let embed = announce::winner_embed(winner, avatar_url.as_deref(), locale);
+    let mention = announce::mention(&redis_pool, keys, winner).await;
+    let message = command
+        .edit_response(
+            &ctx.http,
+            EditInteractionResponse::new()
+                .content(mention)
+                .embed(embed)
+                .components(claims::buttons(winner.number, locale)),
+        )
+        .await?;
+    announce::follow_up(ctx, guild, keys, winner, &message, locale).await
+}
+
+fn draws(guild: &GuildConfig) -> prometheus::IntCounter {
+    metrics::DRAWS.with_label_values(&[&guild.guild_id.to_string()])
+}
+
+async fn animation_names(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    dramatic: bool,
+) -> Vec<String> {
+    if !dramatic {
+        return Vec::new();
+    }
     let mut redis_connection = redis_pool.get().await.unwrap();
+    redis_connection.lrange(&keys.raffle, 0, -1).await.unwrap()
+}
+
+/// Says so when the raffle ran out before `amount` winners were drawn
+fn shortfall(drawn: usize, amount: u64, locale: Locale) -> String {
+    if drawn as u64 >= amount {
+        return String::new();
+    }
+    Msg::FewerWinners { drawn, amount }.text(locale)
+}
+
+/// Content of a summary: mentions of the winners, then the shortfall if any
+fn summary_content(mentions: Option<String>, drawn: usize, amount: u64, locale: Locale) -> String {
+    let shortfall = Some(shortfall(drawn, amount, locale)).filter(|line| !line.is_empty());
+
+    mentions
+        .into_iter()
+        .chain(shortfall)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Draw and announce in a channel. One winner gets their own message with
+/// Claim buttons, more get one summary to settle them from.
+pub async fn draw_into_channel(
+    ctx: &Context,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    channel_id: ChannelId,
+    draw: Draw<'_>,
+    eligibility: &Eligibility,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let rng = type_map_keys::Rng::get(&ctx.data).await;
+    let shutdown = type_map_keys::Shutdown::get(&ctx.data).await;
+    // names to flash during the animation, taken before anyone is drawn
+    let names = animation_names(&redis_pool, keys, draw.dramatic).await;
+
+    let winners = pick_winners(
+        &redis_pool,
+        keys,
+        &rng,
+        eligibility,
+        draw.prize,
+        draw.amount as usize,
+    )
+    .await;
+    let Some(winner) = winners.first() else {
+        return channel_id
+            .send_message(
+                &ctx.http,
+                CreateMessage::new().content(Msg::NoEntries.text(locale)),
+            )
+            .await
+            .map(|_| ());
+    };
+    draws(guild).inc_by(winners.len() as u64);
 
-    let size: usize = redis_connection.llen(redis_key).await.unwrap();
-    let entries = std::cmp::min(size, amount as usize);
+    let message = if draw.dramatic {
+        let message = channel_id
+            .send_message(
+                &ctx.http,
+                CreateMessage::new().content(Msg::Drawing.text(locale)),
+            )
+            .await?;
+        let decoys = {
+            let mut rng = rng.write().await;
+            animation::decoys(&names, &winner.name, guild.draw_animation.frames, &mut *rng)
+        };
+        animation::play(&guild.draw_animation, decoys, &shutdown, |content| {
+            channel_id.edit_message(&ctx.http, message.id, EditMessage::new().content(content))
+        })
+        .await?;
+        Some(message)
+    } else {
+        None
+    };
 
-    match entries {
-        0..=1 => {
-            // will always return 1, since we check size before this
-            if let Some(winner) = pick_winner(&redis_pool, redis_key, ctx).await {
-                command
-                    .create_response(
+    if draw.amount > 1 {
+        let (embed, components) = announce::summary(&winners, 0, locale);
+        let mut mentions = announce::mentions(&redis_pool, keys, &winners)
+            .await
+            .into_iter();
+        let content = summary_content(mentions.next(), winners.len(), draw.amount, locale);
+        let message = match message {
+            Some(message) => {
+                channel_id
+                    .edit_message(
                         &ctx.http,
-                        CreateInteractionResponse::Message(
-                            CreateInteractionResponseMessage::new()
-                                .content(format!("Winner is **{winner}**")),
-                        ),
+                        message.id,
+                        EditMessage::new()
+                            .content(content)
+                            .embed(embed)
+                            .components(components),
                     )
-                    .await?;
-            } else {
-                command
-                    .create_response(
+                    .await?
+            }
+            None => {
+                channel_id
+                    .send_message(
                         &ctx.http,
-                        CreateInteractionResponse::Message(
-                            CreateInteractionResponseMessage::new()
-                                .content("No entries in the raffle."),
-                        ),
+                        CreateMessage::new()
+                            .content(content)
+                            .embed(embed)
+                            .components(components),
                     )
-                    .await?;
+                    .await?
             }
+        };
+        for mentions in mentions {
+            channel_id
+                .send_message(&ctx.http, CreateMessage::new().content(mentions))
+                .await?;
         }
-        _ => {
-            command
-                .create_response(
+        for winner in &winners {
+            announce::follow_up(ctx, guild, keys, winner, &message, locale).await?;
+        }
+        return Ok(());
+    }
+
+    let avatar_url = announce::avatar_url(ctx, guild, winner).await;
+    let embed = announce::winner_embed(winner, avatar_url.as_deref(), locale);
+    let mention = announce::mention(&redis_pool, keys, winner).await;
+    let message = match message {
+        Some(message) => {
+            channel_id
+                .edit_message(
                     &ctx.http,
-                    CreateInteractionResponse::Message(
-                        CreateInteractionResponseMessage::new()
-                            .content(format!("Found {entries} winners")),
-                    ),
+                    message.id,
+                    EditMessage::new()
+                        .content(mention)
+                        .embed(embed)
+                        .components(claims::buttons(winner.number, locale)),
                 )
-                .await?;
-
-            for _ in 0..amount {
-                if let Some(winner) = pick_winner(&redis_pool, redis_key, ctx).await {
-                    command
-                        .channel_id
-                        .send_message(
-                            &ctx.http,
-                            CreateMessage::new().content(format!("Winner: **{winner}**")),
-                        )
-                        .await?;
-                } else {
-                    break;
-                }
-            }
+                .await?
         }
-    }
+        None => {
+            channel_id
+                .send_message(
+                    &ctx.http,
+                    CreateMessage::new()
+                        .content(mention)
+                        .embed(embed)
+                        .components(claims::buttons(winner.number, locale)),
+                )
+                .await?
+        }
+    };
+    announce::follow_up(ctx, guild, keys, winner, &message, locale).await
+}
 
-    Ok(())
+/// Remove a random entry from the raffle, record it as a winner and return it.
+/// Entries that no longer pass the rules are dropped and another is drawn.
+pub async fn pick_winner(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    rng: &RwLock<DrawRng>,
+    eligibility: &Eligibility,
+    prize: Option<&str>,
+) -> Option<Winner> {
+    pick_winners(redis_pool, keys, rng, eligibility, prize, 1)
+        .await
+        .pop()
 }
 
-async fn pick_winner(
+/// Draw up to `amount` winners in one transaction, so they're all taken out
+/// and recorded or none are. Fewer come back when the raffle runs out.
+pub async fn pick_winners(
     redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
-    raffle_redis_key: &str,
-    ctx: &Context,
-) -> Option<String> {
+    keys: &RedisKeys,
+    rng: &RwLock<DrawRng>,
+    eligibility: &Eligibility,
+    prize: Option<&str>,
+    amount: usize,
+) -> Vec<Winner> {
     let mut redis_connection = redis_pool.get().await.unwrap();
-    let size: usize = redis_connection.llen(raffle_redis_key).await.unwrap();
+    loop {
+        // anything else touching these before EXEC makes it fail, and the
+        // draw starts over from what's there now
+        let _: () = redis::cmd("WATCH")
+            .arg(&[&keys.raffle, &keys.entries, &keys.wins, &keys.draw_count])
+            .query_async(&mut *redis_connection)
+            .await
+            .unwrap();
+        let names: Vec<String> = redis_connection.lrange(&keys.raffle, 0, -1).await.unwrap();
+        if names.is_empty() {
+            let _: () = redis::cmd("UNWATCH")
+                .query_async(&mut *redis_connection)
+                .await
+                .unwrap();
+            return Vec::new();
+        }
+        let records: HashMap<String, String> =
+            redis_connection.hgetall(&keys.entries).await.unwrap();
+        let wins: HashMap<String, u32> = redis_connection.hgetall(&keys.wins).await.unwrap();
+        let draw_count: Option<u64> = redis_connection.get(&keys.draw_count).await.unwrap();
 
-    if size == 0 {
-        return None;
-    }
+        let drawn = draw::draw(
+            records::with_records(names, &records),
+            wins,
+            eligibility,
+            amount,
+            &mut *rng.write().await,
+        );
+        let winners: Vec<Winner> = drawn
+            .winners
+            .iter()
+            .zip(draw_count.unwrap_or_default() + 1..)
+            .map(|(entry, number)| Winner::drawn(entry.clone(), number, prize))
+            .collect();
 
-    let index: isize = type_map_keys::Rng::rand(&ctx.data, size).await as isize;
-    let winner: String = redis_connection
-        .lindex(raffle_redis_key, index)
-        .await
-        .unwrap();
-    let _: () = redis_connection
-        .lrem(raffle_redis_key, 1, &winner)
-        .await
-        .unwrap();
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for name in drawn.names() {
+            pipe.lrem(&keys.raffle, 1, name).ignore();
+        }
+        if !winners.is_empty() {
+            let records: Vec<String> = winners
+                .iter()
+                .map(|winner| serde_json::to_string(winner).unwrap())
+                .collect();
+            pipe.rpush(&keys.winners, records)
+                .ignore()
+                .incr(&keys.draw_count, winners.len())
+                .ignore();
+        }
+        for reference in winners
+            .iter()
+            .filter_map(|winner| winner.ticket_reference.as_deref())
+        {
+            pipe.hincr(&keys.wins, reference, 1).ignore();
+        }
+        if !drawn.skips.is_empty() {
+            let skips: Vec<String> = drawn
+                .skips
+                .iter()
+                .map(|skip| serde_json::to_string(skip).unwrap())
+                .collect();
+            pipe.rpush(&keys.skipped, skips).ignore();
+        }
+        let committed: Option<()> = pipe.query_async(&mut *redis_connection).await.unwrap();
+        if committed.is_none() {
+            info!("Raffle changed during a draw, drawing again");
+            continue;
+        }
 
-    Some(winner)
+        for (entry, rule) in &drawn.dropped {
+            info!(
+                "Dropped {} from the raffle, no longer eligible: {:?}",
+                entry.name, rule
+            );
+        }
+        for skip in &drawn.skips {
+            info!(
+                "Skipped {}, at the win cap with {} wins",
+                skip.name, skip.wins
+            );
+        }
+        return winners;
+    }
 }
 
 #[instrument(skip(ctx))]
 pub async fn clear(
     ctx: &Context,
     command: &CommandInteraction,
-    loaded_redis_key: &str,
-    raffle_redis_key: &str,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    locale: Locale,
 ) -> serenity::Result<()> {
-    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
-    let mut redis_connection = redis_pool.get().await.unwrap();
-    let _: () = redis_connection.del(loaded_redis_key).await.unwrap();
-    let _: () = redis_connection.del(raffle_redis_key).await.unwrap();
+    let content = match snapshots::before(ctx, guild, keys, Trigger::Clear).await {
+        Some(refusal) => refusal,
+        None => {
+            let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+            clear_names(&redis_pool, keys).await?;
+            Msg::Cleared
+        }
+    };
 
     command
         .create_response(
             &ctx.http,
             CreateInteractionResponse::Message(
-                CreateInteractionResponseMessage::new().content("Cleared list"),
+                CreateInteractionResponseMessage::new().content(content.text(locale)),
             ),
         )
         .await
 }
 
+/// Remove every entry and winner, including the record of who was already loaded
+pub async fn clear_names(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+) -> serenity::Result<()> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let _: () = redis_connection
+        .del(&[
+            &keys.loaded,
+            &keys.raffle,
+            &keys.entries,
+            &keys.winners,
+            &keys.draw_count,
+            &keys.claims,
+            &keys.claim_deadlines,
+            &keys.skipped,
+        ])
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
 #[instrument(skip(ctx))]
 pub async fn add(
     ctx: &Context,
     command: &CommandInteraction,
-    loaded_redis_key: &str,
-    raffle_redis_key: &str,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
     name: &str,
+    locale: Locale,
 ) -> serenity::Result<()> {
-    add_name(ctx, loaded_redis_key, raffle_redis_key, name).await?;
+    // the rules ask Tito, which can outlast the interaction deadline
+    command.defer(&ctx.http).await?;
+    let content = match eligibility::prepare(ctx, guild, keys).await {
+        Ok(eligibility) => add_checked(ctx, guild, keys, name, &eligibility, locale).await?,
+        Err(err) => {
+            error!("Cannot check eligibility: {}", err);
+            Msg::TitoUnavailable.text(locale)
+        }
+    };
 
     command
-        .create_response(
-            &ctx.http,
-            CreateInteractionResponse::Message(
-                CreateInteractionResponseMessage::new().content(format!("Added {name}")),
-            ),
-        )
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
         .await
+        .map(|_| ())
 }
 
-#[instrument(skip(ctx))]
-pub async fn add_name(
+/// Add a typed name, or the ticket of a picked suggestion, if the exclusion
+/// and eligibility rules let them in
+async fn add_checked(
     ctx: &Context,
-    loaded_redis_key: &str,
-    raffle_redis_key: &str,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
     name: &str,
-) -> serenity::Result<()> {
+    eligibility: &Eligibility,
+    locale: Locale,
+) -> serenity::Result<String> {
+    let tickets = eligibility.tickets();
+    // a picked autocomplete suggestion names a ticket, not the typed text
+    let entry = match name.strip_prefix(autocomplete::TICKET_CHOICE_PREFIX) {
+        Some(reference) => tickets
+            .iter()
+            .find(|ticket| ticket.reference == reference)
+            .and_then(|ticket| {
+                Some(
+                    Entry::new(ticket.full_name()?, EntrySource::Manual)
+                        .ticket_reference(reference)
+                        .release_title(&ticket.release_title),
+                )
+            }),
+        None => Some(Entry::new(name, EntrySource::Manual)),
+    };
+    let Some(entry) = entry else {
+        return Ok(Msg::NoNamedTicket(name).text(locale));
+    };
+
+    let ticket = exclusions::entry_ticket(&entry, tickets);
+    let exclusions = exclusions::ticket_exclusions(ctx, guild, keys, ticket).await;
+    let name = entry.name.clone();
     let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let content = if let Some(reason) = exclusions.entry(&entry, tickets) {
+        Msg::ExcludedEntry {
+            name: &name,
+            reason: &reason.text(locale),
+        }
+        .text(locale)
+    } else if let Some(rule) = eligibility.entry(&entry) {
+        Msg::Ineligible {
+            name: &name,
+            reason: &Msg::BrokenRule(rule).text(locale),
+        }
+        .text(locale)
+    } else if add_entry(&redis_pool, keys, entry).await? {
+        Msg::Added(&name).text(locale)
+    } else {
+        Msg::AlreadyInRaffle(&name).text(locale)
+    };
+
+    Ok(content)
+}
+
+/// Add an entry by name. Returns `false` if the name is already in the raffle.
+#[instrument(skip(redis_pool))]
+pub async fn add_name(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    name: &str,
+) -> serenity::Result<bool> {
+    add_entry(redis_pool, keys, Entry::new(name, EntrySource::Manual)).await
+}
+
+/// Add an entry unless its name is already in the raffle
+#[instrument(skip(redis_pool))]
+pub async fn add_entry(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    entry: Entry,
+) -> serenity::Result<bool> {
     let mut redis_connection = redis_pool.get().await.unwrap();
-    let _: () = redis_connection.sadd(loaded_redis_key, name).await.unwrap();
+    let names: Vec<String> = redis_connection.lrange(&keys.raffle, 0, -1).await.unwrap();
+    if names.contains(&entry.name) {
+        return Ok(false);
+    }
+
+    let _: () = redis_connection
+        .sadd(&keys.loaded, &entry.name)
+        .await
+        .unwrap();
     let _: () = redis_connection
-        .rpush(raffle_redis_key, name)
+        .rpush(&keys.raffle, &entry.name)
+        .await
+        .unwrap();
+    drop(redis_connection);
+    records::save_entries(redis_pool, keys, &[entry])
         .await
         .unwrap();
 
-    Ok(())
+    Ok(true)
 }
 
 #[instrument(skip(ctx))]
 pub async fn size(
     ctx: &Context,
     command: &CommandInteraction,
-    raffle_redis_key: &str,
+    keys: &RedisKeys,
+    locale: Locale,
 ) -> serenity::Result<()> {
     let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
-    let mut redis_connection = redis_pool.get().await.unwrap();
-    let size: usize = redis_connection.llen(raffle_redis_key).await.unwrap();
+    let size = raffle_size(&redis_pool, keys).await?;
 
     command
         .create_response(
             &ctx.http,
             CreateInteractionResponse::Message(
-                CreateInteractionResponseMessage::new()
-                    .content(format!("{size} entries in the raffle")),
+                CreateInteractionResponseMessage::new().content(Msg::RaffleSize(size).text(locale)),
             ),
         )
         .await
 }
+
+/// Number of entries left in the raffle
+pub async fn raffle_size(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+) -> serenity::Result<usize> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let size: usize = redis_connection.llen(&keys.raffle).await.unwrap();
+
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_thousands() {
+        assert_eq!(thousands(0), "0");
+        assert_eq!(thousands(340), "340");
+        assert_eq!(thousands(1200), "1,200");
+        assert_eq!(thousands(1234567), "1,234,567");
+    }
+
+    #[test]
+    fn keys_are_scoped_by_event() {
+        let guild: GuildConfig = serde_json::from_str(
+            r#"{"guild_id": 1, "channel_id": 2, "checkin_list_slug": "chk", "redis_namespace": ""}"#,
+        )
+        .unwrap();
+
+        let keys = RedisKeys::new(&guild, "cosmico-2025");
+        assert_eq!(keys.raffle, "cosmico-2025:raffle");
+        assert_eq!(keys.wins, "cosmico-2025:wins");
+        assert_eq!(RedisKeys::unscoped(&guild).raffle, "raffle");
+
+        let guild = GuildConfig {
+            redis_namespace: Some("cosmico".to_string()),
+            ..guild
+        };
+        assert_eq!(
+            RedisKeys::new(&guild, "cosmico-2025").wins,
+            "cosmico:cosmico-2025:wins"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Slot machine style reveal for `/raffle pick dramatic:True`. The winner is
+//! already drawn and recorded before any of this runs, the names flashed
+//! before it are only for show.
+use crate::{config::DrawAnimation, shutdown::Shutdown};
+use rand::{seq::SliceRandom, Rng};
+use std::future::Future;
+
+/// Names to flash before revealing `winner`, never the same twice in a row.
+/// Empty when nobody else is in the raffle.
+pub fn decoys(names: &[String], winner: &str, frames: usize, rng: &mut impl Rng) -> Vec<String> {
+    let mut others = names
+        .iter()
+        .filter(|name| name.as_str() != winner)
+        .collect::<Vec<&String>>();
+    others.sort();
+    others.dedup();
+
+    let mut decoys: Vec<String> = Vec::with_capacity(frames);
+    if others.is_empty() {
+        return decoys;
+    }
+    while decoys.len() < frames {
+        let name = *others.choose(rng).unwrap();
+        if others.len() > 1 && decoys.last() == Some(name) {
+            continue;
+        }
+        decoys.push(name.clone());
+    }
+
+    decoys
+}
+
+/// Show each decoy with `show`, pausing longer after each one. Cuts straight
+/// to the end if the bot starts shutting down.
+pub async fn play<F, Fut, T>(
+    animation: &DrawAnimation,
+    decoys: Vec<String>,
+    shutdown: &Shutdown,
+    mut show: F,
+) -> serenity::Result<()>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = serenity::Result<T>>,
+{
+    for (decoy, delay) in decoys.into_iter().zip(animation.delays()) {
+        if shutdown.is_stopping() {
+            break;
+        }
+        show(format!("🎰 **{decoy}**")).await?;
+        tokio::time::sleep(delay).await;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn decoys_skip_winner_and_repeats() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let names = ["Foo", "Bar", "Baz", "Foo"].map(String::from);
+
+        let decoys = decoys(&names, "Baz", 20, &mut rng);
+        assert_eq!(decoys.len(), 20);
+        assert!(decoys.iter().all(|name| name != "Baz"));
+        assert!(decoys.windows(2).all(|pair| pair[0] != pair[1]));
+
+        assert!(super::decoys(&names[2..3], "Baz", 20, &mut rng).is_empty());
+    }
+}
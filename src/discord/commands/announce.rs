@@ -0,0 +1,592 @@
+//! Winner announcements. The embed fields are what "Return to raffle" reads
+//! back, so they double as the record of who won in the channel.
+use super::{
+    claims, enter,
+    records::{self, Delivery, Winner, WinnerStatus},
+    RedisKeys,
+};
+use crate::{
+    config::GuildConfig,
+    discord::type_map_keys,
+    i18n::{Locale, Msg},
+    metrics,
+};
+use serenity::{
+    builder::{
+        CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateMessage, CreateSelectMenuOption, EditMessage,
+    },
+    client::Context,
+    model::{
+        application::{ActionRowComponent, ComponentInteraction, ComponentInteractionDataKind},
+        channel::{Embed, Message},
+        id::{ChannelId, MessageId, UserId},
+        mention::Mentionable,
+        Timestamp,
+    },
+};
+use tracing::{instrument, warn};
+
+/// Custom id prefix of the Previous and Next buttons on a summary of winners,
+/// followed by the first and last draw number and the page
+pub const SUMMARY_BUTTON_PREFIX: &str = "raffle_winners:";
+const SUMMARY_PAGE_SIZE: usize = 20;
+/// Room for mentions in a message, under Discord's 2000 characters with space
+/// left for a note about a short draw
+const MENTIONS_LENGTH: usize = 1800;
+/// Discord's limit on the label of a menu option
+const MAX_OPTION_LABEL: usize = 100;
+
+// fields are read back by position, their names are translated
+const NAME_FIELD: usize = 0;
+const PRIZE_FIELD: usize = 2;
+const DRAW_FIELD: usize = 3;
+const WINNER_FIELDS: usize = 4;
+/// Shown in fields left empty
+const NO_VALUE: &str = "-";
+
+pub fn winner_embed(winner: &Winner, avatar_url: Option<&str>, locale: Locale) -> CreateEmbed {
+    let mut embed = CreateEmbed::new()
+        .title(Msg::WinnerTitle.text(locale))
+        .field(Msg::NameField.text(locale), &winner.name, false)
+        .field(
+            Msg::ReleaseField.text(locale),
+            winner.release_title.as_deref().unwrap_or(NO_VALUE),
+            true,
+        )
+        .field(
+            Msg::PrizeField.text(locale),
+            winner.prize.as_deref().unwrap_or(NO_VALUE),
+            true,
+        )
+        .field(Msg::DrawField.text(locale), winner.number.to_string(), true);
+    if let Ok(drawn_at) = Timestamp::from_unix_timestamp(winner.drawn_at.timestamp()) {
+        embed = embed.timestamp(drawn_at);
+    }
+    if let Some(avatar_url) = avatar_url {
+        embed = embed.thumbnail(avatar_url);
+    }
+
+    embed
+}
+
+/// Marks a settled winner in a summary
+fn status_mark(status: WinnerStatus) -> &'static str {
+    match status {
+        WinnerStatus::Pending => "",
+        WinnerStatus::Claimed => " ✅",
+        WinnerStatus::NoShow => " ❌",
+        WinnerStatus::Expired => " ⌛",
+        WinnerStatus::Returned => " ↩️",
+    }
+}
+
+/// Winners on one page of a summary, and the number of pages
+fn page_of(winners: &[Winner], page: usize) -> (&[Winner], usize) {
+    let pages = winners.len().div_ceil(SUMMARY_PAGE_SIZE).max(1);
+    let start = page.min(pages - 1) * SUMMARY_PAGE_SIZE;
+    let end = (start + SUMMARY_PAGE_SIZE).min(winners.len());
+
+    (&winners[start..end], pages)
+}
+
+/// Text of one page of a summary of winners, and the number of pages
+pub fn summary_page(winners: &[Winner], page: usize) -> (String, usize) {
+    let (winners, pages) = page_of(winners, page);
+    let lines: Vec<String> = winners
+        .iter()
+        .map(|winner| {
+            let mark = status_mark(winner.status);
+            match &winner.release_title {
+                Some(release_title) => {
+                    format!("#{} {} · {release_title}{mark}", winner.number, winner.name)
+                }
+                None => format!("#{} {}{mark}", winner.number, winner.name),
+            }
+        })
+        .collect();
+
+    (lines.join("\n"), pages)
+}
+
+/// All winners of a multi-winner draw in one embed, with a menu to settle
+/// each of them and buttons to page through them
+pub fn summary(
+    winners: &[Winner],
+    page: usize,
+    locale: Locale,
+) -> (CreateEmbed, Vec<CreateActionRow>) {
+    let (description, pages) = summary_page(winners, page);
+    let page = page.min(pages - 1);
+    let (first, last) = (
+        winners.first().map_or(0, |winner| winner.number),
+        winners.last().map_or(0, |winner| winner.number),
+    );
+    let ids = |page: usize| format!("{first}:{last}:{page}");
+    let prize = winners.first().and_then(|winner| winner.prize.as_deref());
+    let mut embed = CreateEmbed::new()
+        .title(
+            Msg::WinnersTitle {
+                count: winners.len(),
+                prize,
+            }
+            .text(locale),
+        )
+        .description(description);
+    let mut components = Vec::new();
+    let (shown, _) = page_of(winners, page);
+    if !shown.is_empty() {
+        components.push(claims::settle_menu(&ids(page), shown, locale));
+    }
+    if pages == 1 {
+        return (embed, components);
+    }
+
+    embed = embed.footer(CreateEmbedFooter::new(
+        Msg::Page {
+            page: page + 1,
+            pages,
+        }
+        .text(locale),
+    ));
+    let button = |page: usize| format!("{SUMMARY_BUTTON_PREFIX}{}", ids(page));
+    components.push(CreateActionRow::Buttons(vec![
+        CreateButton::new(button(page.saturating_sub(1)))
+            .label(Msg::PreviousButton.text(locale))
+            .disabled(page == 0),
+        CreateButton::new(button(page + 1))
+            .label(Msg::NextButton.text(locale))
+            .disabled(page + 1 >= pages),
+    ]));
+
+    (embed, components)
+}
+
+/// First and last draw number and the page, from the custom id of a summary's
+/// buttons or menu
+fn parse_ids(ids: &str) -> Option<(u64, u64, usize)> {
+    let mut ids = ids.splitn(3, ':').map(str::parse::<u64>);
+    let (Some(Ok(first)), Some(Ok(last)), Some(Ok(page))) = (ids.next(), ids.next(), ids.next())
+    else {
+        return None;
+    };
+
+    Some((first, last, page as usize))
+}
+
+/// First and last draw number of a summary message, and the page it shows
+pub fn summary_ids(message: &Message) -> Option<(u64, u64, usize)> {
+    message
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::SelectMenu(menu) => menu
+                .custom_id
+                .as_deref()?
+                .strip_prefix(claims::SETTLE_MENU_PREFIX)
+                .and_then(parse_ids),
+            _ => None,
+        })
+}
+
+async fn summary_winners(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    first: u64,
+    last: u64,
+) -> Vec<Winner> {
+    records::winners(redis_pool, keys)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|winner| (first..=last).contains(&winner.number))
+        .collect()
+}
+
+/// Winners on the page a summary message shows. `None` when it isn't a
+/// summary.
+pub async fn shown_winners(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    message: &Message,
+) -> Option<Vec<Winner>> {
+    let (first, last, page) = summary_ids(message)?;
+    let winners = summary_winners(redis_pool, keys, first, last).await;
+
+    Some(page_of(&winners, page).0.to_vec())
+}
+
+/// Show a winner's new status on their summary, turned to the page they're on
+pub async fn refresh_summary(
+    ctx: &Context,
+    keys: &RedisKeys,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    number: u64,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let message = channel_id.message(&ctx.http, message_id).await?;
+    let Some((first, last, _)) = summary_ids(&message) else {
+        return Ok(());
+    };
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let winners = summary_winners(&redis_pool, keys, first, last).await;
+    let page = winners
+        .iter()
+        .position(|winner| winner.number == number)
+        .unwrap_or(0)
+        / SUMMARY_PAGE_SIZE;
+    let (embed, components) = summary(&winners, page, locale);
+
+    channel_id
+        .edit_message(
+            &ctx.http,
+            message_id,
+            EditMessage::new().embed(embed).components(components),
+        )
+        .await
+        .map(|_| ())
+}
+
+/// A winner in a menu, picked by draw number
+pub fn winner_option(winner: &Winner) -> CreateSelectMenuOption {
+    let label: String = format!("#{} {}", winner.number, winner.name)
+        .chars()
+        .take(MAX_OPTION_LABEL)
+        .collect();
+
+    CreateSelectMenuOption::new(label, winner.number.to_string())
+}
+
+/// Draw number picked in a menu of winners
+pub fn selected_draw(component: &ComponentInteraction) -> Option<u64> {
+    match &component.data.kind {
+        ComponentInteractionDataKind::StringSelect { values } => values.first()?.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Turn the page of a summary. `ids` is what follows
+/// [`SUMMARY_BUTTON_PREFIX`].
+#[instrument(skip(ctx))]
+pub async fn summary_button(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    ids: &str,
+) -> serenity::Result<()> {
+    let Some((first, last, page)) = parse_ids(ids) else {
+        return Ok(());
+    };
+
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let winners = summary_winners(&redis_pool, keys, first, last).await;
+    let locale = Locale::resolve(guild.locale, &component.locale);
+    let (embed, buttons) = summary(&winners, page, locale);
+
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(embed)
+                    .components(buttons),
+            ),
+        )
+        .await
+}
+
+/// Value of a field of a single winner's embed, not a summary's
+fn field(embed: &Embed, index: usize) -> Option<&str> {
+    if embed.fields.len() != WINNER_FIELDS {
+        return None;
+    }
+    embed.fields.get(index).map(|field| field.value.as_str())
+}
+
+/// Name of the winner announced in an embed
+pub fn winner_name(embed: &Embed) -> Option<&str> {
+    field(embed, NAME_FIELD)
+}
+
+/// Draw number announced in an embed
+pub fn winner_number(embed: &Embed) -> Option<u64> {
+    field(embed, DRAW_FIELD)?.parse().ok()
+}
+
+/// Prize announced in an embed, if there was one
+pub fn winner_prize(embed: &Embed) -> Option<&str> {
+    field(embed, PRIZE_FIELD).filter(|prize| *prize != NO_VALUE)
+}
+
+/// Discord account linked to the winner's ticket
+async fn linked_user(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    winner: &Winner,
+) -> Option<UserId> {
+    let reference = winner.ticket_reference.as_deref()?;
+    enter::linked_user(redis_pool, keys, reference)
+        .await
+        .unwrap()
+}
+
+/// Mention of the Discord account linked to the winner's ticket, to go in
+/// the announcement's content. Empty when nobody linked it.
+pub async fn mention(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    winner: &Winner,
+) -> String {
+    linked_user(redis_pool, keys, winner)
+        .await
+        .map(|user_id| user_id.mention().to_string())
+        .unwrap_or_default()
+}
+
+/// Mentions of every winner with a linked Discord account, split into
+/// messages that fit. The first goes in the summary's content.
+pub async fn mentions(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    winners: &[Winner],
+) -> Vec<String> {
+    let mut mentions = Vec::new();
+    for winner in winners {
+        let mention = mention(redis_pool, keys, winner).await;
+        if !mention.is_empty() {
+            mentions.push(mention);
+        }
+    }
+
+    chunk_mentions(mentions)
+}
+
+fn chunk_mentions(mentions: Vec<String>) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    for mention in mentions {
+        match chunks.last_mut() {
+            Some(chunk) if chunk.len() + 1 + mention.len() <= MENTIONS_LENGTH => {
+                chunk.push(' ');
+                chunk.push_str(&mention);
+            }
+            _ => chunks.push(mention),
+        }
+    }
+
+    chunks
+}
+
+/// Fill in a winner DM template
+pub fn render_dm(template: &str, winner: &Winner) -> String {
+    template
+        .replace("{name}", &winner.name)
+        .replace("{prize}", winner.prize.as_deref().unwrap_or(NO_VALUE))
+        .replace(
+            "{release}",
+            winner.release_title.as_deref().unwrap_or(NO_VALUE),
+        )
+        .replace("{draw}", &winner.number.to_string())
+}
+
+/// Everything after a winner's announcement is posted: start the claim timer
+/// and DM them.
+pub async fn follow_up(
+    ctx: &Context,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    winner: &Winner,
+    message: &Message,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    claims::start(&redis_pool, keys, guild, winner.number, message).await?;
+
+    dm(ctx, guild, keys, winner, message.channel_id, locale).await
+}
+
+/// DM the winner when the guild has a template and their ticket is linked.
+/// Closed DMs get the details in `channel_id` instead.
+pub async fn dm(
+    ctx: &Context,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    winner: &Winner,
+    channel_id: ChannelId,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let Some(template) = guild.winner_dm_template.as_deref() else {
+        return Ok(());
+    };
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let Some(user_id) = linked_user(&redis_pool, keys, winner).await else {
+        return Ok(());
+    };
+
+    let text = render_dm(template, winner);
+    let sent = match user_id.create_dm_channel(&ctx.http).await {
+        Ok(dm_channel) => {
+            dm_channel
+                .send_message(&ctx.http, CreateMessage::new().content(&text))
+                .await
+        }
+        Err(err) => Err(err),
+    };
+    let delivery = match sent {
+        Ok(_) => Delivery::Sent,
+        Err(err) => {
+            warn!("Cannot DM winner {}: {}", winner.number, err);
+            channel_id
+                .send_message(
+                    &ctx.http,
+                    CreateMessage::new().content(
+                        Msg::DmsClosed {
+                            mention: &user_id.mention().to_string(),
+                            text: &text,
+                        }
+                        .text(locale),
+                    ),
+                )
+                .await?;
+            Delivery::Failed
+        }
+    };
+    records::set_winner_delivery(&redis_pool, keys, winner.number, delivery)
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+/// Avatar of the winner's ticket, when the admin API is configured for the
+/// guild. Any failure just leaves the avatar out.
+pub async fn avatar_url(ctx: &Context, guild: &GuildConfig, winner: &Winner) -> Option<String> {
+    let reference = winner.ticket_reference.as_deref()?;
+    let (account_slug, event_slug) = guild.tito_event()?;
+    let admin_client = type_map_keys::TitoAdminClient::get(&ctx.data).await?;
+
+    // entries only know the reference, the check-in list knows the slug
+    let tito_client = type_map_keys::TitoClient::get(&ctx.data).await;
+    let ticket_cache = type_map_keys::TicketCache::get(&ctx.data).await;
+    let tickets = ticket_cache
+        .tickets(&tito_client, &guild.checkin_list_slug)
+        .await
+        .ok()?;
+    let slug = &tickets
+        .iter()
+        .find(|ticket| ticket.reference == reference)?
+        .slug;
+
+    let ticket = metrics::time_tito(
+        "ticket",
+        admin_client.ticket(account_slug, event_slug, slug).send(),
+    )
+    .await
+    .ok()?;
+    Some(ticket.avatar_url).filter(|url| !url.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::commands::records::WinnerStatus;
+    use chrono::offset::Utc;
+
+    #[test]
+    fn reads_name_back_from_embed() {
+        let winner = Winner {
+            number: 3,
+            name: "Foo **Bar**".into(),
+            prize: Some("Poster".into()),
+            ticket_reference: Some("DDPM-1".into()),
+            release_title: Some("General Admission".into()),
+            drawn_at: Utc::now(),
+            status: WinnerStatus::Pending,
+            delivery: None,
+        };
+        let embed: Embed = serde_json::from_value(
+            serde_json::to_value(winner_embed(&winner, None, Locale::Es)).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(winner_name(&embed), Some("Foo **Bar**"));
+        assert_eq!(winner_prize(&embed), Some("Poster"));
+        assert_eq!(winner_number(&embed), Some(3));
+        assert_eq!(
+            render_dm(
+                "{name}, draw {draw}: pick up your {prize} ({release})",
+                &winner
+            ),
+            "Foo **Bar**, draw 3: pick up your Poster (General Admission)"
+        );
+    }
+
+    #[test]
+    fn pages_summary_of_winners() {
+        let winners: Vec<Winner> = (1..=45)
+            .map(|number| Winner {
+                number,
+                name: format!("Winner {number}"),
+                prize: None,
+                ticket_reference: None,
+                release_title: (number == 41).then(|| "Speaker".into()),
+                drawn_at: Utc::now(),
+                status: WinnerStatus::Pending,
+                delivery: None,
+            })
+            .collect();
+
+        let (text, pages) = summary_page(&winners, 2);
+        assert_eq!(pages, 3);
+        assert!(text.starts_with("#41 Winner 41 · Speaker\n#42 Winner 42"));
+        assert_eq!(text.lines().count(), 5);
+
+        let (text, pages) = summary_page(&winners[..3], 7);
+        assert_eq!(pages, 1);
+        assert_eq!(text, "#1 Winner 1\n#2 Winner 2\n#3 Winner 3");
+    }
+
+    #[test]
+    fn marks_settled_winners_in_summary() {
+        let winners: Vec<Winner> = [WinnerStatus::Claimed, WinnerStatus::Pending]
+            .into_iter()
+            .zip(7..)
+            .map(|(status, number)| Winner {
+                number,
+                name: format!("Winner {number}"),
+                prize: None,
+                ticket_reference: None,
+                release_title: None,
+                drawn_at: Utc::now(),
+                status,
+                delivery: None,
+            })
+            .collect();
+
+        let (text, _) = summary_page(&winners, 0);
+        assert_eq!(text, "#7 Winner 7 ✅\n#8 Winner 8");
+        assert_eq!(parse_ids("7:8:0"), Some((7, 8, 0)));
+        assert_eq!(parse_ids("7:8"), None);
+    }
+
+    #[test]
+    fn splits_mentions_to_fit() {
+        let mentions: Vec<String> = (0..100)
+            .map(|id| format!("<@{}>", 100_000_000_000_000_000u64 + id))
+            .collect();
+
+        let chunks = chunk_mentions(mentions);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= MENTIONS_LENGTH));
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| chunk.split(' ').count())
+                .sum::<usize>(),
+            100
+        );
+        assert!(chunk_mentions(Vec::new()).is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+//! Suggest attendees from the check-in list while typing `/raffle add`
+use super::RedisKeys;
+use crate::{
+    discord::type_map_keys,
+    i18n::{Locale, Msg},
+    tito::checkin::client::checkin_lists_handler::Ticket,
+};
+use bb8_redis::redis::AsyncCommands;
+use serenity::{
+    builder::{CreateAutocompleteResponse, CreateInteractionResponse},
+    client::Context,
+    model::application::CommandInteraction,
+};
+use std::collections::HashSet;
+use tracing::instrument;
+
+/// Marks a chosen suggestion, so `/raffle add` adds the ticket rather than
+/// the typed text
+pub const TICKET_CHOICE_PREFIX: &str = "ticket:";
+/// Discord shows at most 25 choices
+const MAX_CHOICES: usize = 25;
+
+/// Tickets whose name or reference contains `typed`, as (label, value) pairs.
+/// Attendees in `loaded` are marked as entered.
+pub fn suggestions(
+    tickets: &[Ticket],
+    loaded: &HashSet<String>,
+    typed: &str,
+    locale: Locale,
+) -> Vec<(String, String)> {
+    let typed = typed.trim().to_lowercase();
+
+    tickets
+        .iter()
+        .filter_map(|ticket| {
+            let name = ticket.full_name()?;
+            let matches = name.to_lowercase().contains(&typed)
+                || ticket.reference.to_lowercase().contains(&typed);
+            if !matches {
+                return None;
+            }
+
+            let label = if loaded.contains(&name) {
+                Msg::EnteredChoice {
+                    name: &name,
+                    reference: &ticket.reference,
+                }
+                .text(locale)
+            } else {
+                format!("{name} ({})", ticket.reference)
+            };
+            Some((label, format!("{TICKET_CHOICE_PREFIX}{}", ticket.reference)))
+        })
+        .take(MAX_CHOICES)
+        .collect()
+}
+
+#[instrument(skip(ctx))]
+pub async fn add_name(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    keys: &RedisKeys,
+    checkin_list_slug: &str,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let typed = interaction
+        .data
+        .autocomplete()
+        .filter(|option| option.name == "name")
+        .map_or("", |option| option.value);
+
+    let tito_client = type_map_keys::TitoClient::get(&ctx.data).await;
+    let ticket_cache = type_map_keys::TicketCache::get(&ctx.data).await;
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+
+    let mut response = CreateAutocompleteResponse::new();
+    if let Ok(tickets) = ticket_cache.tickets(&tito_client, checkin_list_slug).await {
+        let mut redis_connection = redis_pool.get().await.unwrap();
+        let loaded: HashSet<String> = redis_connection.smembers(&keys.loaded).await.unwrap();
+        for (label, value) in suggestions(&tickets, &loaded, typed, locale) {
+            response = response.add_string_choice(label, value);
+        }
+    }
+
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(response))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(first_name: &str, last_name: &str, reference: &str) -> Ticket {
+        let mut ticket: Ticket =
+            serde_json::from_str(include_str!("../../../fixtures/checkin/ticket.json")).unwrap();
+        ticket.first_name = Some(first_name.to_string());
+        ticket.last_name = Some(last_name.to_string());
+        ticket.reference = reference.to_string();
+        ticket
+    }
+
+    #[test]
+    fn suggests_matching_tickets_and_marks_entered() {
+        let tickets = vec![
+            ticket("Foo", "Bar", "DDPM-1"),
+            ticket("Baz", "Qux", "DDPM-2"),
+            ticket("Foo", "Fighter", "ABCD-1"),
+        ];
+        let loaded = HashSet::from(["Foo Bar".to_string()]);
+
+        assert_eq!(
+            suggestions(&tickets, &loaded, "foo", Locale::En),
+            vec![
+                (
+                    "Foo Bar (DDPM-1) - entered".to_string(),
+                    "ticket:DDPM-1".to_string()
+                ),
+                (
+                    "Foo Fighter (ABCD-1)".to_string(),
+                    "ticket:ABCD-1".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            suggestions(&tickets, &loaded, "ddpm-2", Locale::En).len(),
+            1
+        );
+        assert_eq!(
+            suggestions(&tickets, &loaded, "ddpm-1", Locale::Es)[0].0,
+            "Foo Bar (DDPM-1) - ya participa"
+        );
+    }
+}
@@ -0,0 +1,401 @@
+//! Claim and No-show buttons on winner announcements, the menu that brings
+//! them up for a winner on a summary, and the claim timer that draws a
+//! replacement when nobody comes up
+use super::{
+    announce, draws, eligibility, events, pick_winner,
+    records::{self, Winner, WinnerStatus},
+    RedisKeys,
+};
+use crate::{
+    config::GuildConfig,
+    discord::type_map_keys,
+    i18n::{Locale, Msg},
+};
+use bb8_redis::redis::{self, AsyncCommands};
+use chrono::offset::Utc;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::{
+        CreateActionRow, CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage,
+        CreateMessage, CreateSelectMenu, CreateSelectMenuKind, EditMessage,
+    },
+    client::Context,
+    model::{
+        application::{ButtonStyle, ComponentInteraction},
+        channel::Message,
+        id::{ChannelId, MessageId},
+    },
+};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use tracing::{error, instrument};
+
+/// Custom id prefix of the Claim button, followed by the draw number
+pub const CLAIM_BUTTON_PREFIX: &str = "raffle_claim:";
+/// Custom id prefix of the No-show button, followed by the draw number
+pub const NO_SHOW_BUTTON_PREFIX: &str = "raffle_no_show:";
+/// Custom id prefix of the menu on a summary to settle one of its winners,
+/// followed by the first and last draw number and the page
+pub const SETTLE_MENU_PREFIX: &str = "raffle_settle:";
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// Where a winner yet to claim was announced
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PendingClaim {
+    channel_id: ChannelId,
+    message_id: MessageId,
+    /// Announced with others in a summary, which lists their status
+    #[serde(default)]
+    summary: bool,
+}
+
+pub fn buttons(number: u64, locale: Locale) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{CLAIM_BUTTON_PREFIX}{number}"))
+            .label(Msg::ClaimButton.text(locale))
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("{NO_SHOW_BUTTON_PREFIX}{number}"))
+            .label(Msg::NoShowButton.text(locale))
+            .style(ButtonStyle::Danger),
+    ])]
+}
+
+/// Menu of the winners on a page of a summary. `ids` is what follows
+/// [`SETTLE_MENU_PREFIX`].
+pub fn settle_menu(ids: &str, winners: &[Winner], locale: Locale) -> CreateActionRow {
+    let options = winners
+        .iter()
+        .map(|winner| {
+            announce::winner_option(winner).description(Msg::Status(winner.status).text(locale))
+        })
+        .collect();
+
+    CreateActionRow::SelectMenu(
+        CreateSelectMenu::new(
+            format!("{SETTLE_MENU_PREFIX}{ids}"),
+            CreateSelectMenuKind::String { options },
+        )
+        .placeholder(Msg::SettlePlaceholder.text(locale)),
+    )
+}
+
+/// Wait for the winner announced in `message` to claim. The deadline lives in
+/// Redis, so a restart doesn't forget it.
+pub async fn start(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    guild: &GuildConfig,
+    number: u64,
+    message: &Message,
+) -> serenity::Result<()> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let claim = PendingClaim {
+        channel_id: message.channel_id,
+        message_id: message.id,
+        summary: announce::summary_ids(message).is_some(),
+    };
+    let _: () = redis_connection
+        .hset(&keys.claims, number, serde_json::to_string(&claim).unwrap())
+        .await
+        .unwrap();
+    if guild.claim_timeout_secs > 0 {
+        let deadline = Utc::now().timestamp() + guild.claim_timeout_secs as i64;
+        let _: () = redis_connection
+            .zadd(&keys.claim_deadlines, number, deadline)
+            .await
+            .unwrap();
+    }
+
+    Ok(())
+}
+
+/// Stop waiting on a claim. Only the first caller gets the claim back, so a
+/// button press racing the timer is handled once.
+pub async fn settle(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    number: u64,
+) -> Option<PendingClaim> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let (claim, removed): (Option<String>, usize) = redis::pipe()
+        .hget(&keys.claims, number)
+        .hdel(&keys.claims, number)
+        .zrem(&keys.claim_deadlines, number)
+        .ignore()
+        .query_async(&mut *redis_connection)
+        .await
+        .unwrap();
+
+    if removed == 0 {
+        return None;
+    }
+    serde_json::from_str(&claim?).ok()
+}
+
+pub fn status_line(status: WinnerStatus, name: &str, locale: Locale) -> String {
+    Msg::ClaimStatus { status, name }.text(locale)
+}
+
+/// Bring up Claim and No-show for the winner picked in a summary's menu, only
+/// to whoever picked them
+#[instrument(skip(ctx))]
+pub async fn menu(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+) -> serenity::Result<()> {
+    let Some(number) = announce::selected_draw(component) else {
+        return Ok(());
+    };
+    let locale = Locale::resolve(guild.locale, &component.locale);
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+
+    let response = match records::winner(&redis_pool, keys, number).await.unwrap() {
+        Some(winner) => {
+            let response = CreateInteractionResponseMessage::new()
+                .embed(announce::winner_embed(&winner, None, locale));
+            if winner.status == WinnerStatus::Pending {
+                response.components(buttons(number, locale))
+            } else {
+                response.content(Msg::Status(winner.status).text(locale))
+            }
+        }
+        None => {
+            CreateInteractionResponseMessage::new().content(Msg::UnknownDraw(number).text(locale))
+        }
+    };
+
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(response.ephemeral(true)),
+        )
+        .await
+}
+
+/// Show a settled winner on their announcement. Winners announced alone get
+/// the status in place of their mention.
+async fn mark(
+    ctx: &Context,
+    keys: &RedisKeys,
+    claim: &PendingClaim,
+    number: u64,
+    status: WinnerStatus,
+    name: &str,
+    locale: Locale,
+) -> serenity::Result<()> {
+    if claim.summary {
+        return announce::refresh_summary(
+            ctx,
+            keys,
+            claim.channel_id,
+            claim.message_id,
+            number,
+            locale,
+        )
+        .await;
+    }
+
+    claim
+        .channel_id
+        .edit_message(
+            &ctx.http,
+            claim.message_id,
+            EditMessage::new()
+                .content(status_line(status, name, locale))
+                .components(Vec::new()),
+        )
+        .await
+        .map(|_| ())
+}
+
+/// Handle a press of Claim or No-show, on an announcement or brought up from
+/// a summary's menu
+#[instrument(skip(ctx))]
+pub async fn button(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+) -> serenity::Result<()> {
+    let custom_id = component.data.custom_id.as_str();
+    let (status, number) = if let Some(number) = custom_id.strip_prefix(CLAIM_BUTTON_PREFIX) {
+        (WinnerStatus::Claimed, number)
+    } else if let Some(number) = custom_id.strip_prefix(NO_SHOW_BUTTON_PREFIX) {
+        (WinnerStatus::NoShow, number)
+    } else {
+        return Ok(());
+    };
+    let Ok(number) = number.parse::<u64>() else {
+        return Ok(());
+    };
+
+    let locale = Locale::resolve(guild.locale, &component.locale);
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let Some(claim) = settle(&redis_pool, keys, number).await else {
+        return component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(Msg::AlreadySettled.text(locale))
+                        .ephemeral(true),
+                ),
+            )
+            .await;
+    };
+    records::set_winner_status(&redis_pool, keys, number, status)
+        .await
+        .unwrap();
+
+    let winner = records::winner(&redis_pool, keys, number).await.unwrap();
+    let the_winner = Msg::TheWinner.text(locale);
+    let name = winner.as_ref().map_or(&the_winner, |winner| &winner.name);
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(status_line(status, name, locale))
+                    .components(Vec::new()),
+            ),
+        )
+        .await?;
+    if claim.summary {
+        if let Err(err) = mark(ctx, keys, &claim, number, status, name, locale).await {
+            error!("Cannot update the summary of winner {}: {}", number, err);
+        }
+    }
+
+    if status == WinnerStatus::NoShow {
+        let prize = winner.as_ref().and_then(|winner| winner.prize.as_deref());
+        redraw(ctx, guild, keys, component.channel_id, prize, locale).await?;
+    }
+
+    Ok(())
+}
+
+/// Draw a replacement winner into the channel
+pub async fn redraw(
+    ctx: &Context,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    channel_id: ChannelId,
+    prize: Option<&str>,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let rng = type_map_keys::Rng::get(&ctx.data).await;
+
+    let eligibility = eligibility::for_draw(ctx, guild, keys).await;
+    let Some(winner) = pick_winner(&redis_pool, keys, &rng, &eligibility, prize).await else {
+        return channel_id
+            .send_message(
+                &ctx.http,
+                CreateMessage::new().content(Msg::NoReplacement.text(locale)),
+            )
+            .await
+            .map(|_| ());
+    };
+    draws(guild).inc();
+
+    let avatar_url = announce::avatar_url(ctx, guild, &winner).await;
+    let mention = announce::mention(&redis_pool, keys, &winner).await;
+    let message = channel_id
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .content(mention)
+                .embed(announce::winner_embed(
+                    &winner,
+                    avatar_url.as_deref(),
+                    locale,
+                ))
+                .components(buttons(winner.number, locale)),
+        )
+        .await?;
+    announce::follow_up(ctx, guild, keys, &winner, &message, locale).await
+}
+
+/// Expire claims in the background. Only the first call starts anything,
+/// since `ready` fires again on every reconnect.
+pub fn watch(ctx: Context) {
+    if WATCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            let shutdown = type_map_keys::Shutdown::get(&ctx.data).await;
+            let Some(_in_flight) = shutdown.track() else {
+                break;
+            };
+
+            let config = type_map_keys::Config::get(&ctx.data).await;
+            for guild in config.guilds.values() {
+                if let Err(err) = expire(&ctx, guild).await {
+                    error!("Cannot expire claims for guild {}: {}", guild.guild_id, err);
+                }
+            }
+        }
+    });
+}
+
+/// Mark winners past their deadline and draw replacements
+async fn expire(ctx: &Context, guild: &GuildConfig) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let event = events::current(&redis_pool, guild).await;
+    if event.archived {
+        return Ok(());
+    }
+    let keys = event.keys(guild);
+    let locale = guild.locale.unwrap_or_default();
+    let due: Vec<u64> = {
+        let mut redis_connection = redis_pool.get().await.unwrap();
+        redis_connection
+            .zrangebyscore(&keys.claim_deadlines, 0, Utc::now().timestamp())
+            .await
+            .unwrap()
+    };
+
+    for number in due {
+        let Some(claim) = settle(&redis_pool, &keys, number).await else {
+            continue;
+        };
+        records::set_winner_status(&redis_pool, &keys, number, WinnerStatus::Expired)
+            .await
+            .unwrap();
+        let winner = records::winner(&redis_pool, &keys, number).await.unwrap();
+
+        // the replacement goes out first, the old announcement may be gone
+        let prize = winner.as_ref().and_then(|winner| winner.prize.as_deref());
+        if let Err(err) = redraw(ctx, guild, &keys, claim.channel_id, prize, locale).await {
+            error!("Cannot draw a replacement for winner {}: {}", number, err);
+        }
+
+        let the_winner = Msg::TheWinner.text(locale);
+        let name = winner.as_ref().map_or(&the_winner, |winner| &winner.name);
+        if let Err(err) = mark(
+            ctx,
+            &keys,
+            &claim,
+            number,
+            WinnerStatus::Expired,
+            name,
+            locale,
+        )
+        .await
+        {
+            error!("Cannot mark winner {} as expired: {}", number, err);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,240 @@
+//! The draw itself, worked out in memory on a copy of the raffle. Live draws
+//! write the outcome back in one transaction, rehearsals only show it.
+use super::{
+    eligibility::Eligibility,
+    records::{Entry, Skip},
+    rng, win_cap,
+};
+use crate::eligibility::Rule;
+use chrono::offset::Utc;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// What a draw took out of the raffle
+#[derive(Debug, Default)]
+pub struct Drawn<'a> {
+    /// In the order they were drawn
+    pub winners: Vec<Entry>,
+    /// Entries that no longer pass a rule
+    pub dropped: Vec<(Entry, &'a Rule)>,
+    /// Entries whose ticket is at the win cap
+    pub skips: Vec<Skip>,
+}
+
+impl Drawn<'_> {
+    /// Names to take out of the raffle, one per entry drawn
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.winners
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .chain(self.dropped.iter().map(|(entry, _)| entry.name.as_str()))
+            .chain(self.skips.iter().map(|skip| skip.name.as_str()))
+    }
+}
+
+/// Draw up to `amount` winners from `entries`, in list order. Entries that
+/// can't win are taken out and another is drawn, until the raffle runs out.
+pub fn draw<'a>(
+    mut entries: Vec<Entry>,
+    mut wins: HashMap<String, u32>,
+    eligibility: &'a Eligibility,
+    amount: usize,
+    rng: &mut impl Rng,
+) -> Drawn<'a> {
+    let mut drawn = Drawn::default();
+    while drawn.winners.len() < amount && !entries.is_empty() {
+        let index = rng::pick_index(rng, entries.len());
+        // LREM takes out the first entry with the drawn name
+        let first = entries
+            .iter()
+            .position(|entry| entry.name == entries[index].name)
+            .unwrap_or(index);
+        let entry = entries.remove(first);
+
+        if let Some(rule) = eligibility.entry(&entry) {
+            drawn.dropped.push((entry, rule));
+            continue;
+        }
+        if let (Some(won), Some(cap)) = (
+            win_cap::at_cap(&wins, eligibility.win_cap(), &entry),
+            eligibility.win_cap(),
+        ) {
+            drawn.skips.push(Skip {
+                ticket_reference: entry.ticket_reference.clone().unwrap_or_default(),
+                name: entry.name,
+                wins: won,
+                cap,
+                skipped_at: Utc::now(),
+            });
+            continue;
+        }
+        if let Some(reference) = &entry.ticket_reference {
+            *wins.entry(reference.clone()).or_default() += 1;
+        }
+        drawn.winners.push(entry);
+    }
+
+    drawn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::commands::{records::EntrySource, rng::DrawRng};
+
+    /// Chi-square critical values at p = 0.001, by degrees of freedom. Draws
+    /// are seeded, so a pass is a pass every run.
+    const CRITICAL: [f64; 6] = [0.0, 10.828, 13.816, 16.266, 18.467, 20.515];
+
+    fn entries(names: &[&str]) -> Vec<Entry> {
+        names
+            .iter()
+            .map(|name| Entry::new(*name, EntrySource::Manual))
+            .collect()
+    }
+
+    /// Chi-square of `observed` counts against picks weighted by `weights`
+    fn chi_square(observed: &[usize], weights: &[f64]) -> f64 {
+        let total = observed.iter().sum::<usize>() as f64;
+        let weight: f64 = weights.iter().sum();
+        observed
+            .iter()
+            .zip(weights)
+            .map(|(observed, weight_of)| {
+                let expected = total * weight_of / weight;
+                (*observed as f64 - expected).powi(2) / expected
+            })
+            .sum()
+    }
+
+    fn assert_fair(observed: &[usize], weights: &[f64]) {
+        let chi_square = chi_square(observed, weights);
+        let critical = CRITICAL[observed.len() - 1];
+        assert!(
+            chi_square < critical,
+            "chi-square {chi_square:.2} over {critical} for {observed:?}"
+        );
+    }
+
+    /// How often each name is the `position`th winner over `runs` draws
+    fn winner_counts(
+        entries: &[Entry],
+        names: &[&str],
+        eligibility: &Eligibility,
+        amount: usize,
+        position: usize,
+        runs: usize,
+    ) -> Vec<usize> {
+        let mut rng = DrawRng::seeded(2024);
+        let mut counts = vec![0; names.len()];
+        for _ in 0..runs {
+            let winners = draw(
+                entries.to_vec(),
+                HashMap::new(),
+                eligibility,
+                amount,
+                &mut rng,
+            )
+            .winners;
+            let name = &winners[position].name;
+            counts[names.iter().position(|known| known == name).unwrap()] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn picks_indexes_uniformly() {
+        let mut rng = DrawRng::seeded(1);
+        let mut counts = [0; 6];
+        for _ in 0..60_000 {
+            counts[rng::pick_index(&mut rng, counts.len())] += 1;
+        }
+        assert_fair(&counts, &[1.0; 6]);
+
+        // and the test would notice an off-by-one that never picks the last
+        let mut skewed = [0; 6];
+        for _ in 0..60_000 {
+            skewed[rng::pick_index(&mut rng, skewed.len() - 1)] += 1;
+        }
+        assert!(chi_square(&skewed, &[1.0; 6]) > CRITICAL[5]);
+    }
+
+    #[test]
+    fn one_entry_always_wins_once() {
+        let entries = entries(&["Ada"]);
+        let eligibility = Eligibility::default();
+        for seed in 0..1_000 {
+            let winners = draw(
+                entries.clone(),
+                HashMap::new(),
+                &eligibility,
+                3,
+                &mut DrawRng::seeded(seed),
+            )
+            .winners;
+            assert_eq!(winners.len(), 1);
+            assert_eq!(winners[0].name, "Ada");
+        }
+    }
+
+    #[test]
+    fn duplicate_names_win_by_entries() {
+        let names = ["Ada", "Grace", "Linus"];
+        let entries = entries(&["Ada", "Grace", "Ada", "Linus", "Ada"]);
+        let eligibility = Eligibility::default();
+
+        let first = winner_counts(&entries, &names, &eligibility, 1, 0, 30_000);
+        assert_fair(&first, &[3.0, 1.0, 1.0]);
+
+        // every entry of a name can win, not just the first
+        let winners = draw(
+            entries.clone(),
+            HashMap::new(),
+            &eligibility,
+            5,
+            &mut DrawRng::seeded(3),
+        )
+        .winners;
+        let adas = winners.iter().filter(|winner| winner.name == "Ada").count();
+        assert_eq!(adas, 3);
+    }
+
+    #[test]
+    fn every_pick_of_a_shrinking_pool_is_uniform() {
+        let names = ["Ada", "Grace", "Linus", "Barbara", "Ken", "Margaret"];
+        let entries = entries(&names);
+        let eligibility = Eligibility::default();
+
+        for position in 0..4 {
+            let counts = winner_counts(&entries, &names, &eligibility, 4, position, 30_000);
+            assert_fair(&counts, &[1.0; 6]);
+        }
+    }
+
+    #[test]
+    fn win_cap_shrinks_the_pool_mid_draw() {
+        let names = ["Ada", "Grace", "Linus"];
+        let entries = vec![
+            Entry::new("Ada", EntrySource::Tito).ticket_reference("ADA-1"),
+            Entry::new("Ada", EntrySource::Tito).ticket_reference("ADA-1"),
+            Entry::new("Grace", EntrySource::Tito).ticket_reference("GRACE-1"),
+            Entry::new("Linus", EntrySource::Manual),
+        ];
+        let eligibility = Eligibility::default().with_win_cap(Some(1));
+
+        // Ada's second entry is skipped once Ada won, so three winners at most
+        let mut rng = DrawRng::seeded(5);
+        for _ in 0..1_000 {
+            let winners = draw(entries.clone(), HashMap::new(), &eligibility, 4, &mut rng).winners;
+            let mut won: Vec<&str> = winners.iter().map(|winner| winner.name.as_str()).collect();
+            won.sort_unstable();
+            assert_eq!(won, names);
+        }
+
+        let first = winner_counts(&entries, &names, &eligibility, 1, 0, 30_000);
+        assert_fair(&first, &[2.0, 1.0, 1.0]);
+        let last = winner_counts(&entries, &names, &eligibility, 3, 2, 30_000);
+        // Ada is last only when both of Grace and Linus come first
+        assert_fair(&last, &[2.0 / 12.0, 5.0 / 12.0, 5.0 / 12.0]);
+    }
+}
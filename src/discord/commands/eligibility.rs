@@ -0,0 +1,244 @@
+//! Checking entries against the guild's eligibility [`Rules`] with what Tito
+//! knows right now
+use super::{
+    exclusions,
+    records::{self, Entry, WinnerStatus},
+    RedisKeys,
+};
+use crate::{
+    config::GuildConfig,
+    discord::type_map_keys,
+    eligibility::{Facts, Rule, Rules},
+    metrics,
+    tito::{
+        admin,
+        checkin::client::{checkin_lists_handler::Ticket, Client},
+    },
+};
+use chrono::{offset::Utc, DateTime};
+use serenity::client::Context;
+use std::collections::{HashMap, HashSet};
+use tracing::{error, warn};
+
+/// The admin API and the event to ask it about
+#[derive(Clone, Debug)]
+pub struct AdminEvent<'a> {
+    pub client: admin::client::Client,
+    pub account_slug: &'a str,
+    pub event_slug: &'a str,
+}
+
+impl<'a> AdminEvent<'a> {
+    /// The guild's event, if it and the admin API are set up
+    pub async fn get(ctx: &Context, guild: &'a GuildConfig) -> Option<Self> {
+        let (account_slug, event_slug) = guild.tito_event()?;
+        let client = type_map_keys::TitoAdminClient::get(&ctx.data).await?;
+
+        Some(Self {
+            client,
+            account_slug,
+            event_slug,
+        })
+    }
+}
+
+/// The rules with the Tito data they look at, fetched once
+#[derive(Debug, Default)]
+pub struct Eligibility {
+    rules: Rules,
+    tickets: Vec<Ticket>,
+    /// Earliest check-in that wasn't undone, by ticket id
+    checked_in_at: HashMap<u32, DateTime<Utc>>,
+    /// Admin API tag names, by ticket reference
+    admin_tags: HashMap<String, Vec<String>>,
+    /// Slugs of the questions answered, by ticket id
+    answered: HashMap<u32, Vec<String>>,
+    /// Ticket references and names of past winners
+    winners: HashSet<String>,
+    /// Wins a ticket may have across the event
+    win_cap: Option<u32>,
+}
+
+impl Eligibility {
+    /// Fetch what `rules` need. The admin API is only asked when rules look
+    /// at tags or questions.
+    pub async fn fetch(
+        tito_client: &Client,
+        admin: Option<&AdminEvent<'_>>,
+        redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+        keys: &RedisKeys,
+        checkin_list_slug: &str,
+        rules: Rules,
+    ) -> Result<Self, reqwest::Error> {
+        let (checkins, tickets) = futures::future::try_join(
+            metrics::time_tito(
+                "checkins",
+                tito_client.check_ins(checkin_list_slug).checkins().send(),
+            ),
+            metrics::time_tito(
+                "tickets",
+                tito_client.check_ins(checkin_list_slug).tickets().send(),
+            ),
+        )
+        .await?;
+
+        let mut checked_in_at: HashMap<u32, DateTime<Utc>> = HashMap::new();
+        for checkin in checkins
+            .iter()
+            .filter(|checkin| checkin.deleted_at.is_none())
+        {
+            checked_in_at
+                .entry(checkin.ticket_id)
+                .and_modify(|at| *at = (*at).min(checkin.created_at))
+                .or_insert(checkin.created_at);
+        }
+
+        let questions: Vec<&str> = rules.questions().collect();
+        let mut admin_tags = HashMap::new();
+        let mut answered: HashMap<u32, Vec<String>> = HashMap::new();
+        match admin {
+            Some(admin) => {
+                if rules.needs_tags() {
+                    admin_tags = exclusions::fetch_admin_tags(
+                        &admin.client,
+                        admin.account_slug,
+                        admin.event_slug,
+                    )
+                    .await?;
+                }
+                for question in questions {
+                    let answers = metrics::time_tito(
+                        "answers",
+                        admin
+                            .client
+                            .answers(admin.account_slug, admin.event_slug, question)
+                            .send(),
+                    )
+                    .await?;
+                    for answer in answers {
+                        if answer.response.is_some_and(|response| !response.is_empty()) {
+                            answered
+                                .entry(answer.ticket_id)
+                                .or_default()
+                                .push(question.to_string());
+                        }
+                    }
+                }
+            }
+            None if !questions.is_empty() => {
+                warn!("Question rules need the Tito admin API, nobody passes them without it");
+            }
+            None => {}
+        }
+
+        let winners = records::winners(redis_pool, keys)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|winner| winner.status != WinnerStatus::Returned)
+            .flat_map(|winner| [Some(winner.name), winner.ticket_reference])
+            .flatten()
+            .collect();
+
+        Ok(Self {
+            rules,
+            tickets,
+            checked_in_at,
+            admin_tags,
+            answered,
+            winners,
+            win_cap: None,
+        })
+    }
+
+    /// Skip tickets that won this many times across the event
+    pub fn with_win_cap(mut self, win_cap: Option<u32>) -> Self {
+        self.win_cap = win_cap;
+        self
+    }
+
+    pub fn win_cap(&self) -> Option<u32> {
+        self.win_cap
+    }
+
+    /// Every ticket on the check-in list
+    pub fn tickets(&self) -> &[Ticket] {
+        &self.tickets
+    }
+
+    /// The first rule a ticket breaks
+    pub fn ticket(&self, ticket: &Ticket) -> Option<&Rule> {
+        self.rules.broken(&self.facts(Some(ticket), None))
+    }
+
+    /// The first rule an entry breaks. Its [`exclusions::entry_ticket`] is
+    /// checked when the check-in list has one.
+    pub fn entry(&self, entry: &Entry) -> Option<&Rule> {
+        let ticket = exclusions::entry_ticket(entry, &self.tickets);
+        self.rules.broken(&self.facts(ticket, Some(&entry.name)))
+    }
+
+    fn facts<'a>(&'a self, ticket: Option<&'a Ticket>, name: Option<&str>) -> Facts<'a> {
+        let Some(ticket) = ticket else {
+            return Facts {
+                previous_winner: name.is_some_and(|name| self.winners.contains(name)),
+                ..Default::default()
+            };
+        };
+
+        let checkin_tags = ticket.tags.iter().flat_map(|tags| tags.split(','));
+        let admin_tags = self
+            .admin_tags
+            .get(&ticket.reference)
+            .into_iter()
+            .flatten()
+            .map(String::as_str);
+        Facts {
+            ticket: Some(ticket),
+            checked_in_at: self.checked_in_at.get(&ticket.id).copied(),
+            tags: checkin_tags.chain(admin_tags).map(str::trim).collect(),
+            previous_winner: self.winners.contains(&ticket.reference)
+                || ticket
+                    .full_name()
+                    .is_some_and(|name| self.winners.contains(&name)),
+            answered: self
+                .answered
+                .get(&ticket.id)
+                .into_iter()
+                .flatten()
+                .map(String::as_str)
+                .collect(),
+        }
+    }
+}
+
+/// The guild's rules with fresh Tito data
+pub async fn prepare(
+    ctx: &Context,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+) -> Result<Eligibility, reqwest::Error> {
+    let tito_client = type_map_keys::TitoClient::get(&ctx.data).await;
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let admin = AdminEvent::get(ctx, guild).await;
+
+    Eligibility::fetch(
+        &tito_client,
+        admin.as_ref(),
+        &redis_pool,
+        keys,
+        &guild.checkin_list_slug,
+        guild.rules(),
+    )
+    .await
+    .map(|eligibility| eligibility.with_win_cap(guild.win_cap))
+}
+
+/// Rules to check winners against at draw time. A draw goes ahead with only
+/// the win cap when Tito can't be reached.
+pub async fn for_draw(ctx: &Context, guild: &GuildConfig, keys: &RedisKeys) -> Eligibility {
+    prepare(ctx, guild, keys).await.unwrap_or_else(|err| {
+        error!("Cannot re-check eligibility, drawing without it: {}", err);
+        Eligibility::default().with_win_cap(guild.win_cap)
+    })
+}
@@ -0,0 +1,211 @@
+//! `/raffle enter`, where attendees enter themselves with their ticket and
+//! link their Discord account to it
+use super::{
+    add_entry, eligibility, exclusions,
+    records::{self, Entry, EntrySource, Winner, WinnerStatus},
+    RedisKeys,
+};
+use crate::{
+    config::GuildConfig,
+    discord::type_map_keys,
+    eligibility::Rule,
+    i18n::{Locale, Msg},
+};
+use bb8_redis::redis::{self, AsyncCommands};
+use serenity::{
+    builder::EditInteractionResponse,
+    client::Context,
+    model::{application::CommandInteraction, id::UserId},
+};
+use tracing::instrument;
+
+/// Why a ticket could not be linked to a Discord account
+#[derive(Debug, PartialEq, Eq)]
+pub enum LinkError {
+    /// Another account already linked this ticket
+    TicketTaken,
+    /// This account is linked to a different ticket
+    AlreadyLinked(String),
+}
+
+/// Link a ticket to a Discord account. Linking the same pair again is fine.
+/// Both links are written in one transaction, done again if either changed in
+/// between, so racing calls can't link one account to two tickets.
+pub async fn link(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    reference: &str,
+    user_id: UserId,
+) -> Result<(), LinkError> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let user = user_id.to_string();
+
+    loop {
+        let _: () = redis::cmd("WATCH")
+            .arg(&[&keys.user_tickets, &keys.ticket_users])
+            .query_async(&mut *redis_connection)
+            .await
+            .unwrap();
+        let (linked, owner): (Option<String>, Option<String>) = redis::pipe()
+            .hget(&keys.user_tickets, &user)
+            .hget(&keys.ticket_users, reference)
+            .query_async(&mut *redis_connection)
+            .await
+            .unwrap();
+        let refusal = match (linked, owner) {
+            (Some(linked), _) if linked != reference => Some(LinkError::AlreadyLinked(linked)),
+            (_, Some(owner)) if owner != user => Some(LinkError::TicketTaken),
+            _ => None,
+        };
+        if let Some(refusal) = refusal {
+            let _: () = redis::cmd("UNWATCH")
+                .query_async(&mut *redis_connection)
+                .await
+                .unwrap();
+            return Err(refusal);
+        }
+
+        let committed: Option<()> = redis::pipe()
+            .atomic()
+            .hset(&keys.ticket_users, reference, &user)
+            .ignore()
+            .hset(&keys.user_tickets, &user, reference)
+            .ignore()
+            .query_async(&mut *redis_connection)
+            .await
+            .unwrap();
+        if committed.is_some() {
+            return Ok(());
+        }
+    }
+}
+
+/// Whether a name or ticket was drawn and not returned to the raffle. Drawn
+/// winners are out of the raffle but stay loaded, so entering mustn't put
+/// them back.
+pub fn already_won(winners: &[Winner], name: &str, reference: &str) -> bool {
+    winners.iter().any(|winner| {
+        winner.status != WinnerStatus::Returned
+            && (winner.name == name || winner.ticket_reference.as_deref() == Some(reference))
+    })
+}
+
+/// Discord account linked to a ticket
+pub async fn linked_user(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    reference: &str,
+) -> redis::RedisResult<Option<UserId>> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let user: Option<String> = redis_connection.hget(&keys.ticket_users, reference).await?;
+
+    Ok(user.and_then(|user| user.parse().ok()).map(UserId::new))
+}
+
+#[instrument(skip(ctx))]
+pub async fn enter(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    reference: &str,
+) -> serenity::Result<()> {
+    let reference = reference.trim().to_uppercase();
+    // checking the ticket asks Tito, which can outlast the interaction deadline
+    command.defer_ephemeral(&ctx.http).await?;
+    let content = entry(ctx, command, guild, keys, &reference).await?;
+
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+        .await
+        .map(|_| ())
+}
+
+/// Check the ticket, link it and add the attendee. Returns what to tell them.
+async fn entry(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    reference: &str,
+) -> serenity::Result<String> {
+    let tito_client = type_map_keys::TitoClient::get(&ctx.data).await;
+    let ticket_cache = type_map_keys::TicketCache::get(&ctx.data).await;
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let locale = Locale::resolve(guild.locale, &command.locale);
+
+    let Ok(tickets) = ticket_cache
+        .tickets(&tito_client, &guild.checkin_list_slug)
+        .await
+    else {
+        return Ok(Msg::TitoUnavailable.text(locale));
+    };
+    let Some(ticket) = tickets.iter().find(|ticket| ticket.reference == reference) else {
+        return Ok(Msg::UnknownTicket(reference).text(locale));
+    };
+    let Some(name) = ticket.full_name() else {
+        return Ok(Msg::UnnamedTicket(reference).text(locale));
+    };
+    if exclusions::ticket_exclusions(ctx, guild, keys, Some(ticket))
+        .await
+        .ticket(ticket)
+        .is_some()
+    {
+        return Ok(Msg::TicketExcluded(reference).text(locale));
+    }
+
+    // check-ins change all day, so this skips the cache
+    let Ok(eligibility) = eligibility::prepare(ctx, guild, keys).await else {
+        return Ok(Msg::TitoUnavailable.text(locale));
+    };
+    match eligibility.ticket(ticket) {
+        None => {}
+        Some(Rule::ReleaseIn { .. }) => {
+            return Ok(Msg::IneligibleRelease(&ticket.release_title).text(locale))
+        }
+        Some(Rule::CheckedIn { .. }) => return Ok(Msg::NotCheckedIn(reference).text(locale)),
+        Some(_) => return Ok(Msg::TicketExcluded(reference).text(locale)),
+    }
+    let winners = records::winners(&redis_pool, keys).await.unwrap();
+    if already_won(&winners, &name, reference) {
+        return Ok(Msg::AlreadyWon(&name).text(locale));
+    }
+
+    match link(&redis_pool, keys, reference, command.user.id).await {
+        Ok(()) => {}
+        Err(LinkError::TicketTaken) => return Ok(Msg::TicketTaken(reference).text(locale)),
+        Err(LinkError::AlreadyLinked(linked)) => {
+            return Ok(Msg::AccountLinked(&linked).text(locale))
+        }
+    }
+
+    let entry = Entry::new(&name, EntrySource::Attendee)
+        .ticket_reference(reference)
+        .release_title(&ticket.release_title);
+    if add_entry(&redis_pool, keys, entry).await? {
+        Ok(Msg::Entered(&name).text(locale))
+    } else {
+        Ok(Msg::AlreadyInRaffle(&name).text(locale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_drawn_winners_out() {
+        let mut winner = Winner::drawn(
+            Entry::new("Ada Lovelace", EntrySource::Attendee).ticket_reference("ABCD-1"),
+            1,
+            None,
+        );
+        let winners = std::slice::from_ref(&winner);
+        assert!(already_won(winners, "Ada Lovelace", "EFGH-1"));
+        assert!(already_won(winners, "Ada King", "ABCD-1"));
+        assert!(!already_won(winners, "Grace Hopper", "EFGH-1"));
+
+        winner.status = WinnerStatus::Returned;
+        assert!(!already_won(&[winner], "Ada Lovelace", "ABCD-1"));
+    }
+}
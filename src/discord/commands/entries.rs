@@ -0,0 +1,236 @@
+//! Inspect and prune the entries in the raffle
+use super::{
+    snapshots::{self, Trigger},
+    RedisKeys,
+};
+use crate::{
+    config::GuildConfig,
+    discord::type_map_keys,
+    i18n::{Locale, Msg},
+};
+use bb8_redis::redis::AsyncCommands;
+use serenity::{
+    builder::{
+        CreateActionRow, CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage,
+    },
+    client::Context,
+    model::application::{CommandInteraction, ComponentInteraction},
+};
+use tracing::instrument;
+
+/// Custom id prefix of the Previous and Next buttons, followed by the page
+pub const LIST_BUTTON_PREFIX: &str = "raffle_list:";
+const PAGE_SIZE: usize = 20;
+/// Same as the autocomplete choices, so the reply stays under Discord's
+/// message length
+const MAX_SEARCH_RESULTS: usize = 25;
+
+/// Text of one page of entries, and the number of pages
+pub fn list_page(names: &[String], page: usize, locale: Locale) -> (String, usize) {
+    let pages = names.len().div_ceil(PAGE_SIZE).max(1);
+    let page = page.min(pages - 1);
+    let start = page * PAGE_SIZE;
+
+    let mut content = Msg::EntriesPage {
+        count: names.len(),
+        page: page + 1,
+        pages,
+    }
+    .text(locale);
+    content.push('\n');
+    for (index, name) in names.iter().enumerate().skip(start).take(PAGE_SIZE) {
+        content.push_str(&format!("{}. {name}\n", index + 1));
+    }
+
+    (content, pages)
+}
+
+fn list_message(names: &[String], page: usize, locale: Locale) -> CreateInteractionResponseMessage {
+    let (content, pages) = list_page(names, page, locale);
+    let page = page.min(pages - 1);
+
+    CreateInteractionResponseMessage::new()
+        .content(content)
+        .components(vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(format!("{LIST_BUTTON_PREFIX}{}", page.saturating_sub(1)))
+                .label(Msg::PreviousButton.text(locale))
+                .disabled(page == 0),
+            CreateButton::new(format!("{LIST_BUTTON_PREFIX}{}", page + 1))
+                .label(Msg::NextButton.text(locale))
+                .disabled(page + 1 >= pages),
+        ])])
+}
+
+async fn names(ctx: &Context, keys: &RedisKeys) -> Vec<String> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    redis_connection.lrange(&keys.raffle, 0, -1).await.unwrap()
+}
+
+#[instrument(skip(ctx))]
+pub async fn list(
+    ctx: &Context,
+    command: &CommandInteraction,
+    keys: &RedisKeys,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let names = names(ctx, keys).await;
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(list_message(&names, 0, locale)),
+        )
+        .await
+}
+
+/// Turn the page when Previous or Next is pressed
+#[instrument(skip(ctx))]
+pub async fn list_button(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    keys: &RedisKeys,
+    page: usize,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let names = names(ctx, keys).await;
+
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(list_message(&names, page, locale)),
+        )
+        .await
+}
+
+/// Text listing the first [`MAX_SEARCH_RESULTS`] of the names matching `text`
+pub fn search_results(matches: &[String], text: &str, locale: Locale) -> String {
+    let count = matches.len();
+    let mut content = match count {
+        0 => return Msg::NoMatches(text).text(locale),
+        count if count > MAX_SEARCH_RESULTS => Msg::FirstMatches {
+            shown: MAX_SEARCH_RESULTS,
+            count,
+            text,
+        }
+        .text(locale),
+        count => Msg::Matches { count, text }.text(locale),
+    };
+    content.push('\n');
+    for name in matches.iter().take(MAX_SEARCH_RESULTS) {
+        content.push_str(&format!("- {name}\n"));
+    }
+    content
+}
+
+#[instrument(skip(ctx))]
+pub async fn search(
+    ctx: &Context,
+    command: &CommandInteraction,
+    keys: &RedisKeys,
+    text: &str,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let needle = text.to_lowercase();
+    let matches = names(ctx, keys)
+        .await
+        .into_iter()
+        .filter(|name| name.to_lowercase().contains(&needle))
+        .collect::<Vec<String>>();
+
+    let content = search_results(&matches, text, locale);
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().content(content),
+            ),
+        )
+        .await
+}
+
+/// Take a name out of the raffle and forget it was loaded, so loading again
+/// brings them back. Returns how many entries were removed.
+pub async fn remove_name(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    name: &str,
+) -> serenity::Result<usize> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let removed: usize = redis_connection.lrem(&keys.raffle, 0, name).await.unwrap();
+    let _: () = redis_connection.srem(&keys.loaded, name).await.unwrap();
+    let _: () = redis_connection.hdel(&keys.entries, name).await.unwrap();
+
+    Ok(removed)
+}
+
+#[instrument(skip(ctx))]
+pub async fn remove(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    name: &str,
+) -> serenity::Result<()> {
+    let locale = Locale::resolve(guild.locale, &command.locale);
+    let content = if let Some(refusal) = snapshots::before(ctx, guild, keys, Trigger::Remove).await
+    {
+        refusal.text(locale)
+    } else {
+        let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+        match remove_name(&redis_pool, keys, name).await? {
+            0 => Msg::NotInRaffle(name),
+            _ => Msg::Removed(name),
+        }
+        .text(locale)
+    };
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().content(content),
+            ),
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caps_search_results() {
+        let names: Vec<String> = (1..=30).map(|n| format!("Ada {n}")).collect();
+
+        let content = search_results(&names, "ada", Locale::En);
+        assert!(content.starts_with("First 25 of 30 entries matching \"ada\"\n"));
+        assert!(content.contains("- Ada 25\n"));
+        assert!(!content.contains("Ada 26"));
+
+        let content = search_results(&names[..2], "ada", Locale::En);
+        assert_eq!(content, "2 entries match \"ada\"\n- Ada 1\n- Ada 2\n");
+        assert_eq!(
+            search_results(&[], "ada", Locale::En),
+            "No entries match \"ada\""
+        );
+    }
+
+    #[test]
+    fn pages_entries() {
+        let names = (1..=45)
+            .map(|i| format!("Entry {i}"))
+            .collect::<Vec<String>>();
+
+        let (content, pages) = list_page(&names, 2, Locale::En);
+        assert_eq!(pages, 3);
+        assert!(content.starts_with("**45 entries in the raffle** (page 3 of 3)"));
+        assert!(content.contains("41. Entry 41\n"));
+        assert!(content.ends_with("45. Entry 45\n"));
+
+        let (content, pages) = list_page(&[], 5, Locale::En);
+        assert_eq!(pages, 1);
+        assert!(content.contains("(page 1 of 1)"));
+    }
+}
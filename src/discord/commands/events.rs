@@ -0,0 +1,182 @@
+//! `/event`, which event a guild's raffle keys belong to. Archived events stay
+//! readable until their keys expire.
+use super::{schedule, RedisKeys};
+use crate::{
+    config::GuildConfig,
+    discord::type_map_keys,
+    i18n::{Locale, Msg},
+};
+use bb8_redis::redis::AsyncCommands;
+use chrono::offset::Utc;
+use serenity::{
+    builder::{CreateInteractionResponse, CreateInteractionResponseMessage},
+    client::Context,
+    model::application::CommandInteraction,
+};
+use tracing::{info, instrument};
+
+pub const COMMAND_NAME: &str = "event";
+/// The event `/event start` picked, otherwise the configured one
+const CURRENT_EVENT_REDIS_KEY: &str = "event";
+
+/// The event a guild's commands work on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub id: String,
+    /// Read-only until its keys expire
+    pub archived: bool,
+}
+
+impl Event {
+    pub fn keys(&self, guild: &GuildConfig) -> RedisKeys {
+        RedisKeys::new(guild, &self.id)
+    }
+}
+
+pub async fn current(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    guild: &GuildConfig,
+) -> Event {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let id: Option<String> = redis_connection
+        .get(guild.redis_key(CURRENT_EVENT_REDIS_KEY))
+        .await
+        .unwrap();
+    let id = id.unwrap_or_else(|| guild.event().to_string());
+    let archived: bool = redis_connection
+        .exists(RedisKeys::new(guild, &id).archived)
+        .await
+        .unwrap();
+
+    Event { id, archived }
+}
+
+/// Move keys from before raffles were kept per event into the configured
+/// event. Keys already there are left alone, so this can run on every start.
+pub async fn adopt_unscoped_keys(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    guild: &GuildConfig,
+) {
+    let unscoped = RedisKeys::unscoped(guild);
+    let scoped = RedisKeys::new(guild, guild.event());
+    let mut redis_connection = redis_pool.get().await.unwrap();
+
+    for (from, to) in unscoped.all().into_iter().zip(scoped.all()) {
+        if from == to {
+            continue;
+        }
+        let exists: bool = redis_connection.exists(from).await.unwrap();
+        if exists {
+            let moved: bool = redis_connection.rename_nx(from, to).await.unwrap();
+            if moved {
+                info!("Moved {} to {}", from, to);
+            }
+        }
+    }
+}
+
+async fn respond(
+    ctx: &Context,
+    command: &CommandInteraction,
+    content: String,
+) -> serenity::Result<()> {
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().content(content),
+            ),
+        )
+        .await
+}
+
+/// Switch the guild to another event. Its raffle starts empty unless it ran
+/// before. Schedules stay with the event they were made in.
+#[instrument(skip(ctx))]
+pub async fn start(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    id: &str,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let id = id.trim();
+    if id.is_empty() || id.contains(char::is_whitespace) {
+        return respond(ctx, command, Msg::InvalidEventId(id).text(locale)).await;
+    }
+
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let previous = current(&redis_pool, guild).await;
+    let keys = RedisKeys::new(guild, id);
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let archived: bool = redis_connection.exists(&keys.archived).await.unwrap();
+    if archived {
+        return respond(ctx, command, Msg::EventArchived(id).text(locale)).await;
+    }
+
+    let _: () = redis_connection
+        .set(guild.redis_key(CURRENT_EVENT_REDIS_KEY), id)
+        .await
+        .unwrap();
+    let entries: usize = redis_connection.llen(&keys.raffle).await.unwrap();
+    drop(redis_connection);
+    info!("Guild {} started event {}", guild.guild_id, id);
+
+    let mut content = Msg::EventStarted { id, entries }.text(locale);
+    if previous.id != id {
+        let left = schedule::schedules(&redis_pool, &previous.keys(guild))
+            .await
+            .len();
+        if left > 0 {
+            content.push('\n');
+            content.push_str(
+                &Msg::SchedulesLeft {
+                    count: left,
+                    event: &previous.id,
+                }
+                .text(locale),
+            );
+        }
+    }
+
+    respond(ctx, command, content).await
+}
+
+/// Make the current event read-only and let its keys expire after
+/// `archive_ttl_days`
+#[instrument(skip(ctx))]
+pub async fn archive(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let event = current(&redis_pool, guild).await;
+    if event.archived {
+        return respond(ctx, command, Msg::EventArchived(&event.id).text(locale)).await;
+    }
+
+    let keys = event.keys(guild);
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let _: () = redis_connection
+        .set(&keys.archived, Utc::now().to_rfc3339())
+        .await
+        .unwrap();
+    let content = match guild.archive_ttl_days {
+        0 => Msg::ArchivedForGood(&event.id),
+        days => {
+            let seconds = (days * 24 * 60 * 60) as usize;
+            for key in keys.all() {
+                let _: () = redis_connection.expire(key, seconds).await.unwrap();
+            }
+            Msg::Archived {
+                id: &event.id,
+                days,
+            }
+        }
+    };
+    info!("Guild {} archived event {}", guild.guild_id, event.id);
+
+    respond(ctx, command, content.text(locale)).await
+}
@@ -0,0 +1,486 @@
+//! Keeping staff, volunteers and guests out of the raffle, by the guild's
+//! [`ExclusionRules`] and the `/raffle exclude` list
+use super::{
+    records::{self, Entry},
+    snapshots::{self, Trigger},
+    RedisKeys,
+};
+use crate::{
+    config::{ExclusionRules, GuildConfig},
+    discord::type_map_keys,
+    i18n::{Locale, Msg},
+    metrics,
+    tito::{admin, checkin::client::checkin_lists_handler::Ticket},
+};
+use bb8_redis::redis::{self, AsyncCommands};
+use serenity::{
+    builder::{CreateInteractionResponse, CreateInteractionResponseMessage},
+    client::Context,
+    model::application::CommandInteraction,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::{error, instrument};
+
+/// Why a ticket was kept out
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Reason {
+    /// Listed in the guild's `ticket_references`
+    TicketReference,
+    /// On the `/raffle exclude` list
+    ExcludeList,
+    Tag(String),
+    EmailDomain(String),
+}
+
+impl Reason {
+    pub fn text(&self, locale: Locale) -> String {
+        match self {
+            Self::TicketReference => Msg::ExcludedTicket.text(locale),
+            Self::ExcludeList => Msg::ExcludedByList.text(locale),
+            Self::Tag(tag) => Msg::ExcludedTag(tag).text(locale),
+            Self::EmailDomain(domain) => Msg::ExcludedDomain(domain).text(locale),
+        }
+    }
+}
+
+/// Exclusion rules, ready to check tickets against
+#[derive(Clone, Debug, Default)]
+pub struct Exclusions {
+    rules: ExclusionRules,
+    /// Lowercase ticket references, names and emails from `/raffle exclude`
+    excluded: HashSet<String>,
+    /// Admin API tag names by ticket reference, which the check-in list lacks
+    admin_tags: HashMap<String, Vec<String>>,
+}
+
+impl Exclusions {
+    pub fn new(rules: ExclusionRules, excluded: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            rules,
+            excluded: excluded
+                .into_iter()
+                .map(|value| value.to_lowercase())
+                .collect(),
+            admin_tags: HashMap::new(),
+        }
+    }
+
+    pub fn admin_tags(mut self, admin_tags: HashMap<String, Vec<String>>) -> Self {
+        self.admin_tags = admin_tags;
+        self
+    }
+
+    /// Whether any rule needs tags from the admin API
+    pub fn needs_admin_tags(&self) -> bool {
+        !self.rules.tags.is_empty()
+    }
+
+    /// Why a ticket is kept out of the raffle, if it is
+    pub fn ticket(&self, ticket: &Ticket) -> Option<Reason> {
+        let reference = ticket.reference.to_lowercase();
+        if self
+            .rules
+            .ticket_references
+            .iter()
+            .any(|excluded| excluded.to_lowercase() == reference)
+        {
+            return Some(Reason::TicketReference);
+        }
+
+        let name = ticket.full_name().map(|name| name.to_lowercase());
+        let email = ticket.email.as_deref().map(str::to_lowercase);
+        if [Some(reference), name, email.clone()]
+            .iter()
+            .flatten()
+            .any(|value| self.excluded.contains(value))
+        {
+            return Some(Reason::ExcludeList);
+        }
+
+        let checkin_tags = ticket.tags.iter().flat_map(|tags| tags.split(','));
+        let admin_tags = self
+            .admin_tags
+            .get(&ticket.reference)
+            .into_iter()
+            .flatten()
+            .map(String::as_str);
+        for tag in checkin_tags.chain(admin_tags).map(str::trim) {
+            if let Some(excluded) = self
+                .rules
+                .tags
+                .iter()
+                .find(|excluded| excluded.eq_ignore_ascii_case(tag))
+            {
+                return Some(Reason::Tag(excluded.clone()));
+            }
+        }
+
+        let domain = email.as_deref()?.rsplit_once('@')?.1;
+        self.rules
+            .email_domains
+            .iter()
+            .find(|excluded| {
+                let excluded = excluded.trim_start_matches('@').to_lowercase();
+                domain == excluded || domain.ends_with(&format!(".{excluded}"))
+            })
+            .map(|excluded| Reason::EmailDomain(excluded.clone()))
+    }
+
+    /// Why an entry added by hand is kept out. Its [`entry_ticket`] is checked
+    /// when the check-in list has one.
+    pub fn entry(&self, entry: &Entry, tickets: &[Ticket]) -> Option<Reason> {
+        match entry_ticket(entry, tickets) {
+            Some(ticket) => self.ticket(ticket),
+            None if self.excluded.contains(&entry.name.to_lowercase()) => Some(Reason::ExcludeList),
+            None => None,
+        }
+    }
+}
+
+/// The ticket an entry names, or the ticket with the same name
+pub fn entry_ticket<'t>(entry: &Entry, tickets: &'t [Ticket]) -> Option<&'t Ticket> {
+    match &entry.ticket_reference {
+        Some(reference) => tickets.iter().find(|ticket| ticket.reference == *reference),
+        None => tickets.iter().find(|ticket| {
+            ticket
+                .full_name()
+                .is_some_and(|name| name.eq_ignore_ascii_case(&entry.name))
+        }),
+    }
+}
+
+/// Count of tickets kept out, by reason
+pub type ExclusionReport = BTreeMap<Reason, usize>;
+
+/// Reasons and counts, such as `3 tagged staff, 1 on the exclude list`
+pub fn report_text(report: &ExclusionReport, locale: Locale) -> String {
+    report
+        .iter()
+        .map(|(reason, count)| format!("{count} {}", reason.text(locale)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The `/raffle exclude` list
+pub async fn excluded(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+) -> Vec<String> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let mut excluded: Vec<String> = redis_connection.smembers(&keys.excluded).await.unwrap();
+    excluded.sort();
+    excluded
+}
+
+/// Tag names of every ticket, by reference
+pub async fn fetch_admin_tags(
+    admin_client: &admin::client::Client,
+    account_slug: &str,
+    event_slug: &str,
+) -> Result<HashMap<String, Vec<String>>, reqwest::Error> {
+    let tickets = metrics::time_tito(
+        "admin_tickets",
+        admin_client.tickets(account_slug, event_slug).send(),
+    )
+    .await?;
+
+    Ok(tickets
+        .into_iter()
+        .map(|ticket| (ticket.reference, ticket.tag_names))
+        .collect())
+}
+
+/// The guild's exclusions, with admin API tags of every ticket when the rules
+/// need them and the API is set up
+pub async fn exclusions(ctx: &Context, guild: &GuildConfig, keys: &RedisKeys) -> Exclusions {
+    let exclusions = configured(ctx, guild, keys).await;
+    let Some((admin_client, account_slug, event_slug)) = admin_api(ctx, guild, &exclusions).await
+    else {
+        return exclusions;
+    };
+
+    match fetch_admin_tags(&admin_client, account_slug, event_slug).await {
+        Ok(admin_tags) => exclusions.admin_tags(admin_tags),
+        Err(err) => {
+            // check-in tags still apply
+            error!("Cannot fetch ticket tags: {}", err);
+            exclusions
+        }
+    }
+}
+
+/// Like [`exclusions`], only looking up the admin API tags of one ticket
+pub async fn ticket_exclusions(
+    ctx: &Context,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    ticket: Option<&Ticket>,
+) -> Exclusions {
+    let exclusions = configured(ctx, guild, keys).await;
+    let Some(ticket) = ticket else {
+        return exclusions;
+    };
+    let Some((admin_client, account_slug, event_slug)) = admin_api(ctx, guild, &exclusions).await
+    else {
+        return exclusions;
+    };
+
+    match metrics::time_tito(
+        "ticket",
+        admin_client
+            .ticket(account_slug, event_slug, &ticket.slug)
+            .send(),
+    )
+    .await
+    {
+        Ok(admin_ticket) => exclusions.admin_tags(HashMap::from([(
+            admin_ticket.reference,
+            admin_ticket.tag_names,
+        )])),
+        Err(err) => {
+            error!("Cannot fetch tags of ticket {}: {}", ticket.reference, err);
+            exclusions
+        }
+    }
+}
+
+async fn configured(ctx: &Context, guild: &GuildConfig, keys: &RedisKeys) -> Exclusions {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    Exclusions::new(guild.exclusions.clone(), excluded(&redis_pool, keys).await)
+}
+
+/// The admin API client and event, if the rules need tags and it's set up
+async fn admin_api<'a>(
+    ctx: &Context,
+    guild: &'a GuildConfig,
+    exclusions: &Exclusions,
+) -> Option<(admin::client::Client, &'a str, &'a str)> {
+    if !exclusions.needs_admin_tags() {
+        return None;
+    }
+    let (account_slug, event_slug) = guild.tito_event()?;
+    let admin_client = type_map_keys::TitoAdminClient::get(&ctx.data).await?;
+
+    Some((admin_client, account_slug, event_slug))
+}
+
+#[instrument(skip(ctx))]
+pub async fn add(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    value: &str,
+    locale: Locale,
+) -> serenity::Result<()> {
+    if let Some(refusal) = snapshots::before(ctx, guild, keys, Trigger::Exclude).await {
+        return respond(ctx, command, refusal.text(locale)).await;
+    }
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let value = value.trim();
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let _: () = redis_connection
+        .sadd(&keys.excluded, value.to_lowercase())
+        .await
+        .unwrap();
+    drop(redis_connection);
+
+    let mut content = Msg::ExclusionAdded(value).text(locale);
+    let dropped = drop_excluded(ctx, guild, keys).await;
+    if dropped > 0 {
+        content.push('\n');
+        content.push_str(&Msg::ExcludedEntriesDropped(dropped).text(locale));
+    }
+    respond(ctx, command, content).await
+}
+
+/// Names in the raffle the rules now keep out, once per entry
+pub fn excluded_names(
+    entries: &[Entry],
+    exclusions: &Exclusions,
+    tickets: &[Ticket],
+) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|entry| exclusions.entry(entry, tickets).is_some())
+        .map(|entry| entry.name.clone())
+        .collect()
+}
+
+/// Take entries the exclusion rules now match out of the raffle, so an
+/// exclusion added during the event keeps them from winning. They stay
+/// loaded, so loading again doesn't bring them back. Returns how many were
+/// taken out.
+async fn drop_excluded(ctx: &Context, guild: &GuildConfig, keys: &RedisKeys) -> usize {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let tito_client = type_map_keys::TitoClient::get(&ctx.data).await;
+    let ticket_cache = type_map_keys::TicketCache::get(&ctx.data).await;
+    // names on the exclude list still match without the check-in list
+    let tickets = ticket_cache
+        .tickets(&tito_client, &guild.checkin_list_slug)
+        .await
+        .unwrap_or_else(|err| {
+            error!("Cannot fetch tickets to check exclusions: {}", err);
+            Default::default()
+        });
+    let exclusions = exclusions(ctx, guild, keys).await;
+    let entries = records::entries(&redis_pool, keys).await.unwrap();
+    let names = excluded_names(&entries, &exclusions, &tickets);
+    if names.is_empty() {
+        return 0;
+    }
+
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let mut pipe = redis::pipe();
+    for name in &names {
+        pipe.lrem(&keys.raffle, 1, name).ignore();
+    }
+    let _: () = pipe.query_async(&mut *redis_connection).await.unwrap();
+
+    names.len()
+}
+
+#[instrument(skip(ctx))]
+pub async fn remove(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    value: &str,
+    locale: Locale,
+) -> serenity::Result<()> {
+    if let Some(refusal) = snapshots::before(ctx, guild, keys, Trigger::Exclude).await {
+        return respond(ctx, command, refusal.text(locale)).await;
+    }
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let value = value.trim();
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let removed: usize = redis_connection
+        .srem(&keys.excluded, value.to_lowercase())
+        .await
+        .unwrap();
+
+    let content = if removed > 0 {
+        Msg::ExclusionRemoved(value)
+    } else {
+        Msg::NotExcluded(value)
+    };
+    respond(ctx, command, content.text(locale)).await
+}
+
+#[instrument(skip(ctx))]
+pub async fn list(
+    ctx: &Context,
+    command: &CommandInteraction,
+    keys: &RedisKeys,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let excluded = excluded(&redis_pool, keys).await;
+
+    let content = if excluded.is_empty() {
+        Msg::NoExclusions.text(locale)
+    } else {
+        excluded.join("\n")
+    };
+    respond(ctx, command, content).await
+}
+
+async fn respond(
+    ctx: &Context,
+    command: &CommandInteraction,
+    content: String,
+) -> serenity::Result<()> {
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::commands::records::EntrySource;
+
+    fn ticket(reference: &str, email: &str, tags: Option<&str>) -> Ticket {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "slug": "ti_1",
+            "first_name": "Ada",
+            "last_name": "Lovelace",
+            "email": email,
+            "phone_number": null,
+            "company_name": null,
+            "release_title": "General",
+            "reference": reference,
+            "registration_reference": "REG",
+            "tags": tags,
+            "created_at": "2024-05-04T10:00:00Z",
+            "updated_at": "2024-05-04T10:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn excludes_by_rule() {
+        let rules = ExclusionRules {
+            tags: vec!["Staff".into()],
+            email_domains: vec!["cosmico.mx".into()],
+            ticket_references: vec!["ABCD-1".into()],
+        };
+        let exclusions = Exclusions::new(rules, vec!["EFGH-1".to_string()])
+            .admin_tags(HashMap::from([("WXYZ-1".into(), vec!["staff".into()])]));
+
+        let reason = |reference, email, tags| exclusions.ticket(&ticket(reference, email, tags));
+        assert_eq!(
+            reason("ABCD-1", "ada@example.com", None),
+            Some(Reason::TicketReference)
+        );
+        assert_eq!(
+            reason("EFGH-1", "ada@example.com", None),
+            Some(Reason::ExcludeList)
+        );
+        assert_eq!(
+            reason("IJKL-1", "ada@example.com", Some("vip, staff")),
+            Some(Reason::Tag("Staff".into()))
+        );
+        assert_eq!(
+            reason("WXYZ-1", "ada@example.com", None),
+            Some(Reason::Tag("Staff".into()))
+        );
+        assert_eq!(
+            reason("MNOP-1", "ada@crew.cosmico.mx", None),
+            Some(Reason::EmailDomain("cosmico.mx".into()))
+        );
+        assert_eq!(reason("QRST-1", "ada@notcosmico.mx", None), None);
+
+        let by_name = Exclusions::new(ExclusionRules::default(), vec!["Ada Lovelace".into()]);
+        assert_eq!(
+            by_name.entry(&Entry::new("ada lovelace", EntrySource::Manual), &[]),
+            Some(Reason::ExcludeList)
+        );
+    }
+
+    #[test]
+    fn finds_excluded_entries_in_raffle() {
+        let exclusions = Exclusions::new(ExclusionRules::default(), vec!["ABCD-1".to_string()]);
+        let tickets = [ticket("ABCD-1", "ada@example.com", None)];
+        let entries = [
+            Entry::new("Ada Lovelace", EntrySource::Tito).ticket_reference("ABCD-1"),
+            Entry::new("Grace Hopper", EntrySource::Manual),
+            // added by name, matched to the excluded ticket by its name
+            Entry::new("ada lovelace", EntrySource::Manual),
+        ];
+
+        assert_eq!(
+            excluded_names(&entries, &exclusions, &tickets),
+            vec!["Ada Lovelace".to_string(), "ada lovelace".to_string()]
+        );
+    }
+}
@@ -0,0 +1,514 @@
+//! Export the raffle to a file and bulk add entries from one
+use super::{
+    add_entry,
+    eligibility::{self, Eligibility},
+    exclusions::{self, Exclusions},
+    records::{self, Entry, EntrySource, Winner, WinnerStatus},
+    snapshots::{self, Trigger},
+    RedisKeys,
+};
+use crate::{
+    config::GuildConfig,
+    discord::type_map_keys,
+    i18n::{Locale, Msg},
+};
+use bb8_redis::redis::AsyncCommands;
+use chrono::{offset::Utc, DateTime};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::{
+        CreateAttachment, CreateInteractionResponse, CreateInteractionResponseMessage,
+        EditInteractionResponse,
+    },
+    client::Context,
+    model::{application::CommandInteraction, channel::Attachment},
+};
+use tracing::{error, instrument};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+/// Everything needed to rebuild a raffle
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct RaffleExport {
+    /// Entries left in the raffle, duplicates included
+    pub entries: Vec<Entry>,
+    /// Winners in draw order
+    pub winners: Vec<Winner>,
+    /// Everyone ever added, including past winners
+    pub loaded: Vec<String>,
+}
+
+/// One line of the CSV export. Entries and winners share a sheet so it opens
+/// as a single table.
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    kind: &'static str,
+    name: &'a str,
+    prize: Option<&'a str>,
+    source: Option<EntrySource>,
+    ticket_reference: Option<&'a str>,
+    release_title: Option<&'a str>,
+    /// Only winners have one
+    status: Option<WinnerStatus>,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+impl RaffleExport {
+    pub fn to_csv(&self) -> Result<String, csv::Error> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for entry in &self.entries {
+            writer.serialize(CsvRow {
+                kind: "entry",
+                name: &entry.name,
+                prize: None,
+                source: Some(entry.source),
+                ticket_reference: entry.ticket_reference.as_deref(),
+                release_title: entry.release_title.as_deref(),
+                status: None,
+                timestamp: entry.added_at,
+            })?;
+        }
+        for winner in &self.winners {
+            writer.serialize(CsvRow {
+                kind: "winner",
+                name: &winner.name,
+                prize: winner.prize.as_deref(),
+                source: None,
+                ticket_reference: winner.ticket_reference.as_deref(),
+                release_title: winner.release_title.as_deref(),
+                status: Some(winner.status),
+                timestamp: Some(winner.drawn_at),
+            })?;
+        }
+
+        let bytes = writer.into_inner().map_err(|err| err.into_error())?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn encode(&self, format: Format) -> Result<String, String> {
+        match format {
+            Format::Csv => self.to_csv().map_err(|err| err.to_string()),
+            Format::Json => self.to_json().map_err(|err| err.to_string()),
+        }
+    }
+}
+
+pub async fn export_raffle(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+) -> serenity::Result<RaffleExport> {
+    let entries = records::entries(redis_pool, keys).await.unwrap();
+    let winners = records::winners(redis_pool, keys).await.unwrap();
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let mut loaded: Vec<String> = redis_connection.smembers(&keys.loaded).await.unwrap();
+    loaded.sort();
+
+    Ok(RaffleExport {
+        entries,
+        winners,
+        loaded,
+    })
+}
+
+#[instrument(skip(ctx))]
+pub async fn export(
+    ctx: &Context,
+    command: &CommandInteraction,
+    keys: &RedisKeys,
+    format: Format,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let export = export_raffle(&redis_pool, keys).await?;
+
+    let response = match export.encode(format) {
+        Ok(contents) => CreateInteractionResponseMessage::new()
+            .content(
+                Msg::ExportSummary {
+                    entries: export.entries.len(),
+                    winners: export.winners.len(),
+                }
+                .text(locale),
+            )
+            .add_file(CreateAttachment::bytes(
+                contents.into_bytes(),
+                format!("raffle.{format}"),
+            )),
+        Err(err) => CreateInteractionResponseMessage::new()
+            .content(Msg::ExportFailed(&err.to_string()).text(locale)),
+    };
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await
+}
+
+/// A line of an import file
+#[derive(Debug, PartialEq, Eq)]
+pub enum ImportRow {
+    Name(String),
+    TicketReference(String),
+}
+
+lazy_static! {
+    static ref TICKET_REFERENCE: Regex = Regex::new(r"^[A-Z0-9]{4}-\d+$").unwrap();
+}
+
+/// Read names or ticket references from a CSV. A header row naming `name` or
+/// `reference` columns is used when present, so a CSV export can be imported
+/// again. Without one, the first column of each row is taken, as a ticket
+/// reference when it looks like one.
+pub fn parse_csv(data: &[u8]) -> Result<Vec<ImportRow>, csv::Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(data);
+    let mut records = reader.records().peekable();
+
+    let header = match records.peek() {
+        Some(Ok(first)) => {
+            let columns = first
+                .iter()
+                .map(|column| column.to_lowercase())
+                .collect::<Vec<String>>();
+            let position = |names: &[&str]| {
+                columns
+                    .iter()
+                    .position(|column| names.contains(&column.as_str()))
+            };
+            let header = (
+                position(&["name"]),
+                position(&["reference", "ticket_reference", "ticket reference"]),
+                position(&["kind"]),
+            );
+            (header.0.is_some() || header.1.is_some()).then_some(header)
+        }
+        _ => None,
+    };
+    if header.is_some() {
+        records.next();
+    }
+
+    let mut rows = Vec::new();
+    for record in records {
+        let record = record?;
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|index| record.get(index))
+                .filter(|cell| !cell.is_empty())
+        };
+
+        let row = match header {
+            Some((name, reference, kind)) => {
+                // winners in an export are not entries
+                if cell(kind).is_some_and(|kind| kind != "entry") {
+                    continue;
+                }
+                cell(reference)
+                    .map(|reference| ImportRow::TicketReference(reference.to_string()))
+                    .or_else(|| cell(name).map(|name| ImportRow::Name(name.to_string())))
+            }
+            None => cell(Some(0)).map(|value| {
+                if TICKET_REFERENCE.is_match(value) {
+                    ImportRow::TicketReference(value.to_string())
+                } else {
+                    ImportRow::Name(value.to_string())
+                }
+            }),
+        };
+        rows.extend(row);
+    }
+
+    Ok(rows)
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub added: usize,
+    pub duplicates: usize,
+    /// Ticket references not on the check-in list
+    pub unknown_references: Vec<String>,
+    /// Rows kept out by the exclusion rules, like `/raffle add`
+    pub excluded: usize,
+    /// Rows that break an eligibility rule
+    pub ineligible: usize,
+}
+
+impl ImportReport {
+    pub fn text(&self, locale: Locale) -> String {
+        let mut text = Msg::Imported {
+            added: self.added,
+            duplicates: self.duplicates,
+        }
+        .text(locale);
+        if self.excluded > 0 || self.ineligible > 0 {
+            text.push('\n');
+            text.push_str(
+                &Msg::ImportRejected {
+                    excluded: self.excluded,
+                    ineligible: self.ineligible,
+                }
+                .text(locale),
+            );
+        }
+        if !self.unknown_references.is_empty() {
+            text.push('\n');
+            text.push_str(
+                &Msg::UnknownReferences(&self.unknown_references.join(", ")).text(locale),
+            );
+        }
+
+        text
+    }
+
+    /// Add an entry that passes the exclusion and eligibility rules
+    async fn add(
+        &mut self,
+        redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+        keys: &RedisKeys,
+        exclusions: &Exclusions,
+        eligibility: &Eligibility,
+        entry: Entry,
+    ) -> serenity::Result<()> {
+        if exclusions.entry(&entry, eligibility.tickets()).is_some() {
+            self.excluded += 1;
+        } else if eligibility.entry(&entry).is_some() {
+            self.ineligible += 1;
+        } else if add_entry(redis_pool, keys, entry).await? {
+            self.added += 1;
+        } else {
+            self.duplicates += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Add imported rows to the raffle, looking ticket references up on the
+/// check-in list. Names already in the raffle are skipped, like `add_name`,
+/// and rows are held to the same rules as `/raffle add`.
+pub async fn import_rows(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    exclusions: &Exclusions,
+    eligibility: &Eligibility,
+    rows: Vec<ImportRow>,
+) -> serenity::Result<ImportReport> {
+    let mut report = ImportReport::default();
+    for row in rows {
+        let entry = match row {
+            ImportRow::Name(name) => Entry::new(name, EntrySource::Import),
+            ImportRow::TicketReference(reference) => {
+                let ticket = eligibility
+                    .tickets()
+                    .iter()
+                    .find(|ticket| ticket.reference == reference);
+                match ticket.and_then(|ticket| Some((ticket, ticket.full_name()?))) {
+                    Some((ticket, name)) => Entry::new(name, EntrySource::Import)
+                        .ticket_reference(&ticket.reference)
+                        .release_title(&ticket.release_title),
+                    None => {
+                        report.unknown_references.push(reference);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        report
+            .add(redis_pool, keys, exclusions, eligibility, entry)
+            .await?;
+    }
+
+    Ok(report)
+}
+
+/// Merge a JSON export back in. Entries keep their records, names already in
+/// the raffle are skipped. Winners are not imported.
+pub async fn import_export(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    exclusions: &Exclusions,
+    eligibility: &Eligibility,
+    export: RaffleExport,
+) -> serenity::Result<ImportReport> {
+    let mut report = ImportReport::default();
+    for entry in export.entries {
+        report
+            .add(redis_pool, keys, exclusions, eligibility, entry)
+            .await?;
+    }
+
+    if !export.loaded.is_empty() {
+        let mut redis_connection = redis_pool.get().await.unwrap();
+        let _: () = redis_connection
+            .sadd(&keys.loaded, &export.loaded)
+            .await
+            .unwrap();
+    }
+
+    Ok(report)
+}
+
+#[instrument(skip(ctx))]
+pub async fn import(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    attachment: &Attachment,
+    locale: Locale,
+) -> serenity::Result<()> {
+    // looking up ticket references can outlast the interaction deadline
+    command.defer(&ctx.http).await?;
+
+    let content = match attachment.download().await {
+        Ok(data) => match parse_csv(&data) {
+            Ok(rows) => match eligibility::prepare(ctx, guild, keys).await {
+                Ok(eligibility) => {
+                    if let Some(refusal) =
+                        snapshots::before(ctx, guild, keys, Trigger::Import).await
+                    {
+                        return command
+                            .edit_response(
+                                &ctx.http,
+                                EditInteractionResponse::new().content(refusal.text(locale)),
+                            )
+                            .await
+                            .map(|_| ());
+                    }
+                    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+                    let exclusions = exclusions::exclusions(ctx, guild, keys).await;
+                    import_rows(&redis_pool, keys, &exclusions, &eligibility, rows)
+                        .await?
+                        .text(locale)
+                }
+                Err(err) => {
+                    error!("Cannot check eligibility: {}", err);
+                    Msg::TitoUnavailable.text(locale)
+                }
+            },
+            Err(err) => Msg::ReadFailed {
+                file: &attachment.filename,
+                error: &err.to_string(),
+            }
+            .text(locale),
+        },
+        Err(err) => Msg::DownloadFailed {
+            file: &attachment.filename,
+            error: &err.to_string(),
+        }
+        .text(locale),
+    };
+
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+        .await
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bare_names_and_references() {
+        let rows = parse_csv(b"Foo Bar\nDDPM-1\n\nBaz Qux, extra\n").unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                ImportRow::Name("Foo Bar".into()),
+                ImportRow::TicketReference("DDPM-1".into()),
+                ImportRow::Name("Baz Qux".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_export_csv() {
+        let export = RaffleExport {
+            entries: vec![
+                Entry::new("Foo Bar", EntrySource::Tito).ticket_reference("DDPM-1"),
+                Entry::new("Baz Qux", EntrySource::Manual),
+            ],
+            winners: vec![Winner {
+                number: 1,
+                name: "Won Already".into(),
+                prize: Some("Poster".into()),
+                ticket_reference: None,
+                release_title: None,
+                drawn_at: Utc::now(),
+                status: WinnerStatus::Pending,
+                delivery: None,
+            }],
+            loaded: Vec::new(),
+        };
+        let rows = parse_csv(export.to_csv().unwrap().as_bytes()).unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                ImportRow::TicketReference("DDPM-1".into()),
+                ImportRow::Name("Baz Qux".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn exports_winner_tickets() {
+        let mut winner = Winner::drawn(
+            Entry::new("Foo Bar", EntrySource::Tito)
+                .ticket_reference("DDPM-1")
+                .release_title("General"),
+            1,
+            Some("Poster"),
+        );
+        winner.status = WinnerStatus::Claimed;
+        let export = RaffleExport {
+            winners: vec![winner],
+            ..Default::default()
+        };
+        let csv = export.to_csv().unwrap();
+        let mut lines = csv.lines();
+
+        assert_eq!(
+            lines.next(),
+            Some("kind,name,prize,source,ticket_reference,release_title,status,timestamp")
+        );
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("winner,Foo Bar,Poster,,DDPM-1,General,claimed,"));
+    }
+
+    #[test]
+    fn reports_rejected_rows() {
+        let report = ImportReport {
+            added: 2,
+            duplicates: 1,
+            excluded: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            report.text(Locale::En),
+            "Imported 2 entries, skipped 1 already in the raffle.\nRejected 3 excluded and 0 ineligible."
+        );
+        assert!(!ImportReport::default()
+            .text(Locale::En)
+            .contains("Rejected"));
+    }
+}
@@ -0,0 +1,196 @@
+//! `/raffle odds` and `/raffle history`, what the next draw looks like and
+//! what the past ones did
+use super::{
+    records::{self, Entry, Skip, Winner},
+    win_cap, RedisKeys,
+};
+use crate::{
+    config::GuildConfig,
+    discord::type_map_keys,
+    i18n::{Locale, Msg},
+};
+use chrono::{offset::Utc, DateTime};
+use chrono_tz::Tz;
+use serenity::{
+    builder::{CreateInteractionResponse, CreateInteractionResponseMessage},
+    client::Context,
+    model::application::CommandInteraction,
+};
+use std::collections::HashMap;
+use tracing::instrument;
+
+/// Lines of `/raffle history`, the latest are kept
+const HISTORY_LINES: usize = 25;
+const MAX_CAPPED_NAMES: usize = 20;
+
+/// Text of `/raffle odds`, optionally for one name
+pub fn odds_text(
+    entries: &[Entry],
+    wins: &HashMap<String, u32>,
+    cap: Option<u32>,
+    name: Option<&str>,
+    locale: Locale,
+) -> String {
+    let capped: Vec<(&Entry, u32)> = entries
+        .iter()
+        .filter_map(|entry| Some((entry, win_cap::at_cap(wins, cap, entry)?)))
+        .collect();
+    let eligible = entries.len() - capped.len();
+
+    let mut lines = vec![Msg::Odds {
+        entries: entries.len(),
+        eligible,
+    }
+    .text(locale)];
+    match name {
+        Some(name) => {
+            let own = entries.iter().filter(|entry| entry.name == name).count();
+            let line = match capped.iter().find(|(entry, _)| entry.name == name) {
+                _ if own == 0 => Msg::NotInRaffle(name),
+                Some((_, wins)) => Msg::CappedEntry {
+                    name,
+                    wins: *wins,
+                    cap: cap.unwrap_or_default(),
+                },
+                None => Msg::EntryOdds {
+                    name,
+                    entries: own,
+                    percent: own as f64 / eligible as f64 * 100.0,
+                },
+            };
+            lines.push(line.text(locale));
+        }
+        None if !capped.is_empty() => {
+            let mut names = capped
+                .iter()
+                .take(MAX_CAPPED_NAMES)
+                .map(|(entry, _)| entry.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ");
+            if capped.len() > MAX_CAPPED_NAMES {
+                names.push_str(&format!(", +{}", capped.len() - MAX_CAPPED_NAMES));
+            }
+            lines.push(Msg::AtWinCap(&names).text(locale));
+        }
+        None => {}
+    }
+
+    lines.join("\n")
+}
+
+/// Winners and skipped entries in the order they were drawn
+pub fn history_lines(winners: &[Winner], skips: &[Skip], tz: Tz, locale: Locale) -> Vec<String> {
+    let time = |at: DateTime<Utc>| at.with_timezone(&tz).format("%H:%M").to_string();
+    let mut lines: Vec<(DateTime<Utc>, String)> = winners
+        .iter()
+        .map(|winner| {
+            let line = Msg::HistoryWin {
+                number: winner.number,
+                name: &winner.name,
+                prize: winner.prize.as_deref(),
+                status: &Msg::Status(winner.status).text(locale),
+            }
+            .text(locale);
+            (winner.drawn_at, line)
+        })
+        .chain(skips.iter().map(|skip| {
+            let line = Msg::HistorySkip {
+                name: &skip.name,
+                wins: skip.wins,
+                cap: skip.cap,
+            }
+            .text(locale);
+            (skip.skipped_at, line)
+        }))
+        .collect();
+    lines.sort_by_key(|(at, _)| *at);
+
+    lines
+        .into_iter()
+        .map(|(at, line)| format!("`{}` {line}", time(at)))
+        .collect()
+}
+
+async fn respond(
+    ctx: &Context,
+    command: &CommandInteraction,
+    content: String,
+) -> serenity::Result<()> {
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().content(content),
+            ),
+        )
+        .await
+}
+
+#[instrument(skip(ctx))]
+pub async fn odds(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    name: Option<&str>,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let entries = records::entries(&redis_pool, keys).await.unwrap();
+    let wins = win_cap::wins(&redis_pool, keys).await.unwrap();
+
+    respond(
+        ctx,
+        command,
+        odds_text(&entries, &wins, guild.win_cap, name, locale),
+    )
+    .await
+}
+
+#[instrument(skip(ctx))]
+pub async fn history(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let winners = records::winners(&redis_pool, keys).await.unwrap();
+    let skips = records::skips(&redis_pool, keys).await.unwrap();
+
+    let lines = history_lines(&winners, &skips, guild.time_zone, locale);
+    let content = if lines.is_empty() {
+        Msg::NoHistory.text(locale)
+    } else {
+        lines[lines.len().saturating_sub(HISTORY_LINES)..].join("\n")
+    };
+
+    respond(ctx, command, content).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::commands::records::EntrySource;
+
+    #[test]
+    fn capped_entries_have_no_odds() {
+        let entries = vec![
+            Entry::new("Ada", EntrySource::Tito).ticket_reference("ADA-1"),
+            Entry::new("Grace", EntrySource::Tito).ticket_reference("GRACE-1"),
+            Entry::new("Linus", EntrySource::Manual),
+        ];
+        let wins = HashMap::from([("ADA-1".to_string(), 2), ("GRACE-1".to_string(), 1)]);
+
+        let text = odds_text(&entries, &wins, Some(2), None, Locale::En);
+        assert!(text.starts_with("3 entries, 2 can win."));
+        assert!(text.ends_with("At the win cap, draws skip them: Ada"));
+
+        let text = odds_text(&entries, &wins, Some(2), Some("Grace"), Locale::En);
+        assert!(text.ends_with("Grace has 1 entries, a 50.0% chance on the next draw."));
+
+        let text = odds_text(&entries, &wins, None, Some("Ada"), Locale::En);
+        assert!(text.starts_with("3 entries, 3 can win."));
+    }
+}
@@ -0,0 +1,219 @@
+//! "Return to raffle", a message context-menu command that puts an announced
+//! winner back into the raffle
+use super::{
+    add_entry, announce, claims,
+    records::{self, Winner, WinnerStatus},
+    RedisKeys,
+};
+use crate::{
+    config::GuildConfig,
+    discord::type_map_keys,
+    i18n::{self, Locale, Msg},
+};
+use serenity::{
+    builder::{
+        CreateActionRow, CreateCommand, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateSelectMenu, CreateSelectMenuKind,
+        CreateSelectMenuOption, EditMessage,
+    },
+    client::Context,
+    model::{
+        application::{CommandInteraction, CommandType, ComponentInteraction, ResolvedTarget},
+        id::MessageId,
+    },
+};
+use tracing::instrument;
+
+pub const COMMAND_NAME: &str = "Return to raffle";
+
+pub fn command() -> CreateCommand {
+    let mut command = CreateCommand::new(COMMAND_NAME).kind(CommandType::Message);
+    for (locale, name) in i18n::localizations(COMMAND_NAME) {
+        command = command.name_localized(locale, name);
+    }
+
+    command
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Returned {
+    /// No winner was drawn with that number
+    UnknownDraw,
+    /// Back in the raffle
+    Added(String),
+    /// Returned before, or added again by hand
+    AlreadyInRaffle(String),
+}
+
+/// Put a drawn winner back into the raffle with their original entry record.
+/// Returning the same winner twice does nothing the second time.
+pub async fn return_winner(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    number: u64,
+) -> serenity::Result<Returned> {
+    // nobody needs to claim, so the timer mustn't draw a replacement
+    claims::settle(redis_pool, keys, number).await;
+    // a returned win doesn't count towards the win cap, which only the first
+    // return takes off
+    let Some(winner) = records::set_winner_returned(redis_pool, keys, number)
+        .await
+        .unwrap()
+    else {
+        return Ok(Returned::UnknownDraw);
+    };
+    // only the return that changed the status puts them back
+    if winner.status == WinnerStatus::Returned {
+        return Ok(Returned::AlreadyInRaffle(winner.name));
+    }
+
+    // the record is gone if they were removed since, the draw still knows
+    let mut entry = records::entry(redis_pool, keys, &winner.name)
+        .await
+        .unwrap();
+    if entry.ticket_reference.is_none() {
+        entry.ticket_reference = winner.ticket_reference;
+        entry.release_title = winner.release_title;
+    }
+    if add_entry(redis_pool, keys, entry).await? {
+        Ok(Returned::Added(winner.name))
+    } else {
+        Ok(Returned::AlreadyInRaffle(winner.name))
+    }
+}
+
+/// Custom id prefix of the menu of winners on a summary to return, followed
+/// by the summary's message id
+pub const RETURN_MENU_PREFIX: &str = "raffle_return:";
+
+#[instrument(skip(ctx))]
+pub async fn return_to_raffle(
+    ctx: &Context,
+    command: &CommandInteraction,
+    keys: &RedisKeys,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let bot_id = type_map_keys::UserId::get(&ctx.data).await;
+    let message = match command.data.target() {
+        Some(ResolvedTarget::Message(message)) if message.author.id == bot_id => Some(message),
+        _ => None,
+    };
+    let number =
+        message.and_then(|message| message.embeds.iter().find_map(announce::winner_number));
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+
+    let (content, ephemeral) = match (message, number) {
+        (Some(message), Some(number)) => match return_winner(&redis_pool, keys, number).await? {
+            Returned::Added(name) => {
+                message
+                    .channel_id
+                    .edit_message(
+                        &ctx.http,
+                        message.id,
+                        EditMessage::new()
+                            .content(claims::status_line(WinnerStatus::Returned, &name, locale))
+                            .components(Vec::new()),
+                    )
+                    .await?;
+                (Msg::ReturnedToRaffle(&name).text(locale), false)
+            }
+            Returned::AlreadyInRaffle(name) => (Msg::AlreadyInRaffle(&name).text(locale), true),
+            Returned::UnknownDraw => (Msg::UnknownDraw(number).text(locale), true),
+        },
+        // a summary has no single winner, so ask which one
+        (Some(message), None) => match announce::shown_winners(&redis_pool, keys, message).await {
+            Some(winners) => {
+                return command
+                    .create_response(
+                        &ctx.http,
+                        CreateInteractionResponse::Message(
+                            return_menu(message.id, &winners, locale).ephemeral(true),
+                        ),
+                    )
+                    .await;
+            }
+            None => (Msg::NotAnnouncement.text(locale), true),
+        },
+        _ => (Msg::NotAnnouncement.text(locale), true),
+    };
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(ephemeral),
+            ),
+        )
+        .await
+}
+
+/// Menu of the winners on a summary's page that aren't back in the raffle
+fn return_menu(
+    message_id: MessageId,
+    winners: &[Winner],
+    locale: Locale,
+) -> CreateInteractionResponseMessage {
+    let options: Vec<CreateSelectMenuOption> = winners
+        .iter()
+        .filter(|winner| winner.status != WinnerStatus::Returned)
+        .map(announce::winner_option)
+        .collect();
+    if options.is_empty() {
+        return CreateInteractionResponseMessage::new().content(Msg::NothingToReturn.text(locale));
+    }
+
+    CreateInteractionResponseMessage::new().components(vec![CreateActionRow::SelectMenu(
+        CreateSelectMenu::new(
+            format!("{RETURN_MENU_PREFIX}{message_id}"),
+            CreateSelectMenuKind::String { options },
+        )
+        .placeholder(Msg::ReturnPlaceholder.text(locale)),
+    )])
+}
+
+/// Return the winner picked in the menu and show it on their summary.
+/// `message_id` is what follows [`RETURN_MENU_PREFIX`].
+#[instrument(skip(ctx))]
+pub async fn menu(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    message_id: &str,
+) -> serenity::Result<()> {
+    let (Some(number), Ok(message_id)) = (
+        announce::selected_draw(component),
+        message_id.parse::<MessageId>(),
+    ) else {
+        return Ok(());
+    };
+    let locale = Locale::resolve(guild.locale, &component.locale);
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+
+    let returned = return_winner(&redis_pool, keys, number).await?;
+    let content = match &returned {
+        Returned::Added(name) => Msg::ReturnedToRaffle(name),
+        Returned::AlreadyInRaffle(name) => Msg::AlreadyInRaffle(name),
+        Returned::UnknownDraw => Msg::UnknownDraw(number),
+    }
+    .text(locale);
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(Vec::new()),
+            ),
+        )
+        .await?;
+
+    if let Returned::Added(_) = returned {
+        announce::refresh_summary(ctx, keys, component.channel_id, message_id, number, locale)
+            .await?;
+    }
+
+    Ok(())
+}
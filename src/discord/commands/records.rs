@@ -0,0 +1,335 @@
+//! What is stored about entries and winners beyond their names
+use super::RedisKeys;
+use bb8_redis::redis::{self, AsyncCommands};
+use chrono::{offset::Utc, DateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, strum::Display,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum EntrySource {
+    /// Loaded from the Tito check-in list
+    Tito,
+    /// Added with `/raffle add`
+    Manual,
+    /// Bulk added from a file
+    Import,
+    /// Entered themselves with `/raffle enter`
+    Attendee,
+    /// Added before entry records were kept
+    Unknown,
+}
+
+/// An entry in the raffle, keyed by name
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Entry {
+    pub name: String,
+    pub source: EntrySource,
+    #[serde(default)]
+    pub ticket_reference: Option<String>,
+    #[serde(default)]
+    pub release_title: Option<String>,
+    #[serde(default)]
+    pub added_at: Option<DateTime<Utc>>,
+}
+
+impl Entry {
+    pub fn new(name: impl Into<String>, source: EntrySource) -> Self {
+        Self {
+            name: name.into(),
+            source,
+            ticket_reference: None,
+            release_title: None,
+            added_at: Some(Utc::now()),
+        }
+    }
+
+    pub fn ticket_reference(mut self, reference: impl Into<String>) -> Self {
+        self.ticket_reference = Some(reference.into());
+        self
+    }
+
+    pub fn release_title(mut self, release_title: impl Into<String>) -> Self {
+        self.release_title = Some(release_title.into());
+        self
+    }
+
+    /// Stand-in for names without a stored record
+    fn unknown(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            source: EntrySource::Unknown,
+            ticket_reference: None,
+            release_title: None,
+            added_at: None,
+        }
+    }
+}
+
+/// Whether a winner came up to collect their prize
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, strum::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum WinnerStatus {
+    /// Waiting for the winner to claim
+    #[default]
+    Pending,
+    Claimed,
+    /// An organizer pressed No-show
+    NoShow,
+    /// Nobody claimed before the claim timer ran out
+    Expired,
+    /// Put back into the raffle with "Return to raffle"
+    Returned,
+}
+
+/// Whether the winner got their direct message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, strum::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Delivery {
+    Sent,
+    /// Usually closed DMs, they were mentioned in the channel instead
+    Failed,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Winner {
+    /// Counts up from 1 across the raffle
+    #[serde(default)]
+    pub number: u64,
+    pub name: String,
+    #[serde(default)]
+    pub prize: Option<String>,
+    #[serde(default)]
+    pub ticket_reference: Option<String>,
+    #[serde(default)]
+    pub release_title: Option<String>,
+    pub drawn_at: DateTime<Utc>,
+    #[serde(default)]
+    pub status: WinnerStatus,
+    /// How the winner's direct message went. `None` when none was sent.
+    #[serde(default)]
+    pub delivery: Option<Delivery>,
+}
+
+impl Winner {
+    /// A drawn entry as winner number `number`, waiting to claim
+    pub fn drawn(entry: Entry, number: u64, prize: Option<&str>) -> Self {
+        Self {
+            number,
+            name: entry.name,
+            prize: prize.map(str::to_string),
+            ticket_reference: entry.ticket_reference,
+            release_title: entry.release_title,
+            drawn_at: Utc::now(),
+            status: WinnerStatus::Pending,
+            delivery: None,
+        }
+    }
+}
+
+/// An entry passed over by a draw because their ticket is at the win cap
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Skip {
+    pub name: String,
+    pub ticket_reference: String,
+    /// Wins across the event when they were drawn
+    pub wins: u32,
+    pub cap: u32,
+    pub skipped_at: DateTime<Utc>,
+}
+
+/// Store entry records. An existing record for the same name is kept, so a
+/// re-added winner keeps where they originally came from.
+pub async fn save_entries(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    entries: &[Entry],
+) -> redis::RedisResult<()> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let mut pipe = redis::pipe();
+    for entry in entries {
+        pipe.hset_nx(
+            &keys.entries,
+            &entry.name,
+            serde_json::to_string(entry).unwrap(),
+        )
+        .ignore();
+    }
+
+    pipe.query_async(&mut *redis_connection).await
+}
+
+/// The stored record of a name
+pub async fn entry(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    name: &str,
+) -> redis::RedisResult<Entry> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let record: Option<String> = redis_connection.hget(&keys.entries, name).await?;
+
+    Ok(record
+        .and_then(|record| serde_json::from_str(&record).ok())
+        .unwrap_or_else(|| Entry::unknown(name)))
+}
+
+/// Entries left in the raffle, in list order. Duplicate names are repeated.
+pub async fn entries(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+) -> redis::RedisResult<Vec<Entry>> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let names: Vec<String> = redis_connection.lrange(&keys.raffle, 0, -1).await?;
+    let records: HashMap<String, String> = redis_connection.hgetall(&keys.entries).await?;
+
+    Ok(with_records(names, &records))
+}
+
+/// Entries for raffle names, from the stored records by name
+pub fn with_records(names: Vec<String>, records: &HashMap<String, String>) -> Vec<Entry> {
+    names
+        .into_iter()
+        .map(|name| {
+            records
+                .get(&name)
+                .and_then(|record| serde_json::from_str(record).ok())
+                .unwrap_or_else(|| Entry::unknown(name))
+        })
+        .collect()
+}
+
+/// Every entry passed over, oldest first
+pub async fn skips(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+) -> redis::RedisResult<Vec<Skip>> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let skips: Vec<String> = redis_connection.lrange(&keys.skipped, 0, -1).await?;
+
+    Ok(skips
+        .iter()
+        .filter_map(|skip| serde_json::from_str(skip).ok())
+        .collect())
+}
+
+/// Every winner drawn, oldest first
+pub async fn winners(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+) -> redis::RedisResult<Vec<Winner>> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let winners: Vec<String> = redis_connection.lrange(&keys.winners, 0, -1).await?;
+
+    Ok(winners
+        .iter()
+        .filter_map(|winner| serde_json::from_str(winner).ok())
+        .collect())
+}
+
+/// A drawn winner by draw number
+pub async fn winner(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    number: u64,
+) -> redis::RedisResult<Option<Winner>> {
+    Ok(winners(redis_pool, keys)
+        .await?
+        .into_iter()
+        .find(|winner| winner.number == number))
+}
+
+/// Update the status of a drawn winner by draw number
+pub async fn set_winner_status(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    number: u64,
+    status: WinnerStatus,
+) -> redis::RedisResult<()> {
+    update_winner(redis_pool, keys, number, |winner, _| winner.status = status)
+        .await
+        .map(|_| ())
+}
+
+/// Record how the winner's direct message went
+pub async fn set_winner_delivery(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    number: u64,
+    delivery: Delivery,
+) -> redis::RedisResult<()> {
+    update_winner(redis_pool, keys, number, |winner, _| {
+        winner.delivery = Some(delivery)
+    })
+    .await
+    .map(|_| ())
+}
+
+/// Mark a winner as returned to the raffle, taking their win off the win cap
+/// the first time. Returns the winner as they were.
+pub async fn set_winner_returned(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    number: u64,
+) -> redis::RedisResult<Option<Winner>> {
+    update_winner(redis_pool, keys, number, |winner, pipe| {
+        if winner.status == WinnerStatus::Returned {
+            return;
+        }
+        if let Some(reference) = &winner.ticket_reference {
+            pipe.hincr(&keys.wins, reference, -1).ignore();
+        }
+        winner.status = WinnerStatus::Returned;
+    })
+    .await
+}
+
+/// Change a winner in place, along with anything `update` adds to the same
+/// transaction. A concurrent update of the same list makes the write fail,
+/// and it is done again on the fresh record. Returns the winner as they were.
+async fn update_winner(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    number: u64,
+    update: impl Fn(&mut Winner, &mut redis::Pipeline),
+) -> redis::RedisResult<Option<Winner>> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    loop {
+        let _: () = redis::cmd("WATCH")
+            .arg(&keys.winners)
+            .query_async(&mut *redis_connection)
+            .await?;
+        let winners: Vec<String> = redis_connection.lrange(&keys.winners, 0, -1).await?;
+        let found = winners.iter().enumerate().find_map(|(index, winner)| {
+            serde_json::from_str::<Winner>(winner)
+                .ok()
+                .filter(|winner| winner.number == number)
+                .map(|winner| (index, winner))
+        });
+        let Some((index, previous)) = found else {
+            let _: () = redis::cmd("UNWATCH")
+                .query_async(&mut *redis_connection)
+                .await?;
+            return Ok(None);
+        };
+
+        let mut winner = previous.clone();
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        update(&mut winner, &mut pipe);
+        pipe.lset(
+            &keys.winners,
+            index as isize,
+            serde_json::to_string(&winner).unwrap(),
+        )
+        .ignore();
+        let committed: Option<()> = pipe.query_async(&mut *redis_connection).await?;
+        if committed.is_some() {
+            return Ok(Some(previous));
+        }
+    }
+}
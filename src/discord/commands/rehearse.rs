@@ -0,0 +1,113 @@
+//! `/raffle rehearse`, a draw from a copy of the raffle with a seed. The same
+//! seed on the same raffle picks the same winners, and nobody is drawn.
+use super::{
+    draw,
+    eligibility::{self, Eligibility},
+    records::{self, Entry},
+    rng::DrawRng,
+    win_cap, RedisKeys,
+};
+use crate::{
+    config::GuildConfig,
+    discord::type_map_keys,
+    i18n::{Locale, Msg},
+};
+use rand::Rng;
+use serenity::{
+    builder::EditInteractionResponse, client::Context, model::application::CommandInteraction,
+};
+use std::collections::HashMap;
+use tracing::instrument;
+
+/// The winners a live draw would pick with the same `rng`, without taking
+/// anyone out of the raffle
+pub fn rehearse(
+    entries: Vec<Entry>,
+    wins: HashMap<String, u32>,
+    eligibility: &Eligibility,
+    amount: usize,
+    rng: &mut impl Rng,
+) -> Vec<Entry> {
+    draw::draw(entries, wins, eligibility, amount, rng).winners
+}
+
+#[instrument(skip(ctx))]
+pub async fn rehearse_draw(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    seed: Option<u64>,
+    amount: usize,
+    locale: Locale,
+) -> serenity::Result<()> {
+    // re-checking the rules asks Tito, which can outlast the interaction deadline
+    command.defer(&ctx.http).await?;
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let eligibility = eligibility::for_draw(ctx, guild, keys).await;
+    let entries = records::entries(&redis_pool, keys).await.unwrap();
+    let wins = win_cap::wins(&redis_pool, keys).await.unwrap();
+
+    let seed = seed
+        .or(guild.rehearsal_seed)
+        .unwrap_or_else(DrawRng::new_seed);
+    let winners = rehearse(
+        entries,
+        wins,
+        &eligibility,
+        amount,
+        &mut DrawRng::seeded(seed),
+    );
+    let content = if winners.is_empty() {
+        Msg::NoEntries.text(locale)
+    } else {
+        let names = winners
+            .iter()
+            .enumerate()
+            .map(|(index, winner)| format!("{}. {}", index + 1, winner.name))
+            .collect::<Vec<String>>()
+            .join("\n");
+        Msg::Rehearsal {
+            seed,
+            winners: &names,
+        }
+        .text(locale)
+    };
+
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+        .await
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discord::commands::records::EntrySource;
+
+    #[test]
+    fn same_seed_same_winners() {
+        let entries: Vec<Entry> = ["Ada", "Grace", "Linus", "Ada", "Barbara", "Ken"]
+            .into_iter()
+            .map(|name| Entry::new(name, EntrySource::Manual))
+            .collect();
+        let eligibility = Eligibility::default();
+        let draw = |seed| {
+            rehearse(
+                entries.clone(),
+                HashMap::new(),
+                &eligibility,
+                4,
+                &mut DrawRng::seeded(seed),
+            )
+            .into_iter()
+            .map(|winner| winner.name)
+            .collect::<Vec<String>>()
+        };
+
+        let winners = draw(42);
+        assert_eq!(winners.len(), 4);
+        assert_eq!(winners, draw(42));
+        assert!((0..20).any(|seed| draw(seed) != winners));
+    }
+}
@@ -0,0 +1,67 @@
+//! Where draws get their randomness. Live draws ask the OS, rehearsals use a
+//! seed so the same draw comes out again.
+use rand::{
+    rngs::{OsRng, StdRng},
+    CryptoRng, Rng, RngCore, SeedableRng,
+};
+
+/// Seeds above this can't be typed back into a Discord integer option
+pub const MAX_SEED: u64 = (1 << 53) - 1;
+
+#[derive(Clone, Debug)]
+pub enum DrawRng {
+    Os(OsRng),
+    Seeded(Box<StdRng>),
+}
+
+impl DrawRng {
+    pub fn os() -> Self {
+        Self::Os(OsRng)
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        Self::Seeded(Box::new(StdRng::seed_from_u64(seed)))
+    }
+
+    /// A seed for a rehearsal that didn't pick one
+    pub fn new_seed() -> u64 {
+        OsRng.gen_range(0..=MAX_SEED)
+    }
+}
+
+impl RngCore for DrawRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            Self::Os(rng) => rng.next_u32(),
+            Self::Seeded(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            Self::Os(rng) => rng.next_u64(),
+            Self::Seeded(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            Self::Os(rng) => rng.fill_bytes(dest),
+            Self::Seeded(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        match self {
+            Self::Os(rng) => rng.try_fill_bytes(dest),
+            Self::Seeded(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
+impl CryptoRng for DrawRng {}
+
+/// Position of the next winner in a raffle of `size` entries
+pub fn pick_index(rng: &mut impl Rng, size: usize) -> usize {
+    rng.gen_range(0..size)
+}
@@ -0,0 +1,378 @@
+//! `/raffle schedule`, draws that run by themselves at a set time. Schedules
+//! live in Redis, so a restart picks them back up.
+use super::{draw_into_channel, eligibility, events, Draw, RedisKeys};
+use crate::{
+    config::GuildConfig,
+    discord::type_map_keys,
+    i18n::{Locale, Msg},
+};
+use bb8_redis::redis::AsyncCommands;
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::{CreateInteractionResponse, CreateInteractionResponseMessage},
+    client::Context,
+    model::application::CommandInteraction,
+};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use tracing::{error, instrument};
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(10);
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M %Z";
+
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// A draw waiting for its time
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Schedule {
+    pub id: u64,
+    pub next_run: DateTime<Utc>,
+    pub amount: u64,
+    pub prize: Option<String>,
+    #[serde(default)]
+    pub dramatic: bool,
+    /// Minutes between runs. Runs once when unset.
+    pub repeat_minutes: Option<u64>,
+}
+
+impl Schedule {
+    fn draw(&self) -> Draw<'_> {
+        Draw {
+            amount: self.amount,
+            prize: self.prize.as_deref(),
+            dramatic: self.dramatic,
+        }
+    }
+
+    /// The first run after `now`, skipping any missed while the bot was down.
+    /// `None` once a one-off schedule has run.
+    fn following(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let every =
+            ChronoDuration::minutes(self.repeat_minutes.filter(|minutes| *minutes > 0)? as i64);
+        let missed = (now - self.next_run).num_minutes().max(0) / every.num_minutes();
+
+        Some(self.next_run + every * (missed as i32 + 1))
+    }
+}
+
+/// Parse `HH:MM`, the next time the clock shows it, or `YYYY-MM-DD HH:MM`, in
+/// the event's time zone
+pub fn parse_at(at: &str, time_zone: Tz, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let at = at.trim();
+    if let Ok(time) = NaiveTime::parse_from_str(at, "%H:%M") {
+        let today = now.with_timezone(&time_zone).date_naive();
+        let run = time_zone
+            .from_local_datetime(&today.and_time(time))
+            .earliest()?
+            .with_timezone(&Utc);
+        if run > now {
+            return Some(run);
+        }
+        let tomorrow = today.succ_opt()?;
+        return time_zone
+            .from_local_datetime(&tomorrow.and_time(time))
+            .earliest()
+            .map(|run| run.with_timezone(&Utc));
+    }
+
+    let local = NaiveDateTime::parse_from_str(at, "%Y-%m-%d %H:%M").ok()?;
+    time_zone
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|run| run.with_timezone(&Utc))
+}
+
+/// Every schedule of a guild, soonest first
+pub async fn schedules(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+) -> Vec<Schedule> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let schedules: HashMap<u64, String> = redis_connection.hgetall(&keys.schedules).await.unwrap();
+
+    let mut schedules: Vec<Schedule> = schedules
+        .values()
+        .filter_map(|schedule| serde_json::from_str(schedule).ok())
+        .collect();
+    schedules.sort_by_key(|schedule| (schedule.next_run, schedule.id));
+    schedules
+}
+
+async fn save(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    schedule: &Schedule,
+) {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let _: () = redis_connection
+        .hset(
+            &keys.schedules,
+            schedule.id,
+            serde_json::to_string(schedule).unwrap(),
+        )
+        .await
+        .unwrap();
+}
+
+/// Remove a schedule. Only the first caller gets `true`, so a cancel racing
+/// the run is handled once.
+async fn take(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    id: u64,
+) -> bool {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let removed: usize = redis_connection.hdel(&keys.schedules, id).await.unwrap();
+
+    removed > 0
+}
+
+/// A time as organizers read it, in the event's time zone
+pub fn local_time(run: DateTime<Utc>, time_zone: Tz) -> String {
+    run.with_timezone(&time_zone)
+        .format(TIME_FORMAT)
+        .to_string()
+}
+
+#[instrument(skip(ctx))]
+pub async fn pick(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    at: &str,
+    draw: Draw<'_>,
+    repeat_minutes: Option<u64>,
+) -> serenity::Result<()> {
+    let locale = Locale::resolve(guild.locale, &command.locale);
+    let now = Utc::now();
+
+    let content = match parse_at(at, guild.time_zone, now) {
+        None => Msg::InvalidScheduleTime(at).text(locale),
+        Some(run) if run <= now => Msg::ScheduleInPast.text(locale),
+        Some(next_run) => {
+            let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+            let id: u64 = {
+                let mut redis_connection = redis_pool.get().await.unwrap();
+                redis_connection
+                    .incr(&keys.schedule_count, 1)
+                    .await
+                    .unwrap()
+            };
+            let schedule = Schedule {
+                id,
+                next_run,
+                amount: draw.amount,
+                prize: draw.prize.map(str::to_string),
+                dramatic: draw.dramatic,
+                repeat_minutes,
+            };
+            save(&redis_pool, keys, &schedule).await;
+
+            Msg::Scheduled {
+                id,
+                at: &local_time(next_run, guild.time_zone),
+            }
+            .text(locale)
+        }
+    };
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().content(content),
+            ),
+        )
+        .await
+}
+
+#[instrument(skip(ctx))]
+pub async fn list(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let locale = Locale::resolve(guild.locale, &command.locale);
+    let schedules = schedules(&redis_pool, keys).await;
+
+    let content = if schedules.is_empty() {
+        Msg::NoSchedules.text(locale)
+    } else {
+        schedules
+            .iter()
+            .map(|schedule| {
+                Msg::ScheduleLine {
+                    id: schedule.id,
+                    at: &local_time(schedule.next_run, guild.time_zone),
+                    amount: schedule.amount,
+                    prize: schedule.prize.as_deref(),
+                    repeat_minutes: schedule.repeat_minutes,
+                }
+                .text(locale)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().content(content),
+            ),
+        )
+        .await
+}
+
+#[instrument(skip(ctx))]
+pub async fn cancel(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    id: u64,
+) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let locale = Locale::resolve(guild.locale, &command.locale);
+
+    let content = if take(&redis_pool, keys, id).await {
+        Msg::ScheduleCancelled(id).text(locale)
+    } else {
+        Msg::UnknownSchedule(id).text(locale)
+    };
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().content(content),
+            ),
+        )
+        .await
+}
+
+/// Run schedules in the background. Only the first call starts anything,
+/// since `ready` fires again on every reconnect.
+pub fn watch(ctx: Context) {
+    if WATCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            let shutdown = type_map_keys::Shutdown::get(&ctx.data).await;
+            let Some(_in_flight) = shutdown.track() else {
+                break;
+            };
+
+            let config = type_map_keys::Config::get(&ctx.data).await;
+            for guild in config.guilds.values() {
+                if let Err(err) = run_due(&ctx, guild).await {
+                    error!("Cannot run schedules for guild {}: {}", guild.guild_id, err);
+                }
+            }
+        }
+    });
+}
+
+/// Draw every schedule whose time has come
+async fn run_due(ctx: &Context, guild: &GuildConfig) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let event = events::current(&redis_pool, guild).await;
+    if event.archived {
+        return Ok(());
+    }
+    let keys = event.keys(guild);
+    let now = Utc::now();
+
+    for mut schedule in schedules(&redis_pool, &keys).await {
+        if schedule.next_run > now {
+            break;
+        }
+        // move it out of the way before drawing, so a crash mid-draw doesn't
+        // run it twice
+        if !take(&redis_pool, &keys, schedule.id).await {
+            continue;
+        }
+        if let Some(next_run) = schedule.following(now) {
+            schedule.next_run = next_run;
+            save(&redis_pool, &keys, &schedule).await;
+        }
+
+        scheduled_draw(ctx, guild, &keys, schedule.draw()).await?;
+    }
+
+    Ok(())
+}
+
+/// Post a draw to the guild's channel the way `/raffle pick` would reply
+async fn scheduled_draw(
+    ctx: &Context,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    draw: Draw<'_>,
+) -> serenity::Result<()> {
+    let locale = guild.locale.unwrap_or_default();
+    let eligibility = eligibility::for_draw(ctx, guild, keys).await;
+    draw_into_channel(
+        ctx,
+        guild,
+        keys,
+        guild.channel_id,
+        draw,
+        &eligibility,
+        locale,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_times_in_the_event_time_zone() {
+        let time_zone: Tz = "America/Mexico_City".parse().unwrap();
+        // 12:00 in Mexico City
+        let now = Utc.with_ymd_and_hms(2024, 5, 4, 18, 0, 0).unwrap();
+
+        assert_eq!(
+            parse_at("14:30", time_zone, now),
+            Some(Utc.with_ymd_and_hms(2024, 5, 4, 20, 30, 0).unwrap())
+        );
+        // already past today, so tomorrow
+        assert_eq!(
+            parse_at("09:00", time_zone, now),
+            Some(Utc.with_ymd_and_hms(2024, 5, 5, 15, 0, 0).unwrap())
+        );
+        assert_eq!(
+            parse_at("2024-05-05 10:00", time_zone, now),
+            Some(Utc.with_ymd_and_hms(2024, 5, 5, 16, 0, 0).unwrap())
+        );
+        assert_eq!(parse_at("noon", time_zone, now), None);
+
+        let schedule = Schedule {
+            id: 1,
+            next_run: Utc.with_ymd_and_hms(2024, 5, 4, 15, 0, 0).unwrap(),
+            amount: 1,
+            prize: None,
+            dramatic: false,
+            repeat_minutes: Some(60),
+        };
+        // runs missed while down are skipped
+        assert_eq!(
+            schedule.following(now),
+            Some(Utc.with_ymd_and_hms(2024, 5, 4, 19, 0, 0).unwrap())
+        );
+    }
+}
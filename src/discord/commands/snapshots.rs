@@ -0,0 +1,716 @@
+//! Copies of a guild's raffle state, taken before destructive commands and on
+//! a timer, that `/raffle restore` rolls back to
+use super::{
+    events,
+    records::{Winner, WinnerStatus},
+    schedule, RedisKeys,
+};
+use crate::{
+    config::{GuildConfig, SnapshotConfig, SnapshotStore},
+    discord::type_map_keys,
+    i18n::{Locale, Msg},
+};
+use bb8_redis::redis::{self, AsyncCommands};
+use chrono::{offset::Utc, DateTime, Duration as ChronoDuration};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::{
+        CreateActionRow, CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage,
+    },
+    client::Context,
+    model::application::{ButtonStyle, CommandInteraction, ComponentInteraction},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use tracing::{error, info, instrument};
+
+/// Custom id prefix of the restore buttons, followed by the snapshot id or
+/// `cancel`
+pub const RESTORE_BUTTON_PREFIX: &str = "raffle_restore:";
+const CANCEL: &str = "cancel";
+const WATCH_INTERVAL: Duration = Duration::from_secs(60);
+/// Names listed in a restore preview
+const PREVIEW_NAMES: usize = 10;
+/// Newest snapshots listed by `/raffle snapshots`, so the list stays under
+/// Discord's message length
+const LISTED_SNAPSHOTS: usize = 15;
+
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// What a snapshot was taken before
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    Timer,
+    Clear,
+    Remove,
+    Restore,
+    Import,
+    /// `/raffle exclude add` or `remove`
+    Exclude,
+}
+
+/// Every raffle key of a guild, as stored. Schedules are left out, so a
+/// restore can't bring back draws that already ran.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RaffleState {
+    pub loaded: Vec<String>,
+    pub raffle: Vec<String>,
+    pub entries: BTreeMap<String, String>,
+    pub winners: Vec<String>,
+    pub draw_count: Option<u64>,
+    pub claims: BTreeMap<String, String>,
+    pub claim_deadlines: Vec<(String, i64)>,
+    pub skipped: Vec<String>,
+    pub excluded: Vec<String>,
+}
+
+impl RaffleState {
+    pub async fn read(
+        redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+        keys: &RedisKeys,
+    ) -> redis::RedisResult<Self> {
+        let mut redis_connection = redis_pool.get().await.unwrap();
+        let mut loaded: Vec<String> = redis_connection.smembers(&keys.loaded).await?;
+        loaded.sort();
+        let mut excluded: Vec<String> = redis_connection.smembers(&keys.excluded).await?;
+        excluded.sort();
+
+        Ok(Self {
+            loaded,
+            raffle: redis_connection.lrange(&keys.raffle, 0, -1).await?,
+            entries: redis_connection.hgetall(&keys.entries).await?,
+            winners: redis_connection.lrange(&keys.winners, 0, -1).await?,
+            draw_count: redis_connection.get(&keys.draw_count).await?,
+            claims: redis_connection.hgetall(&keys.claims).await?,
+            claim_deadlines: redis_connection
+                .zrange_withscores(&keys.claim_deadlines, 0, -1)
+                .await?,
+            skipped: redis_connection.lrange(&keys.skipped, 0, -1).await?,
+            excluded,
+        })
+    }
+
+    /// Replace the guild's raffle with this state in one transaction. The
+    /// event's win counts follow the winners that come and go.
+    pub async fn write(
+        &self,
+        redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+        keys: &RedisKeys,
+    ) -> redis::RedisResult<()> {
+        let current = Self::read(redis_pool, keys).await?;
+        let pipe = self.restore_pipe(&current, keys);
+
+        let mut redis_connection = redis_pool.get().await.unwrap();
+        pipe.query_async(&mut *redis_connection).await
+    }
+
+    /// The transaction that replaces `current` with this state. Claims are
+    /// cleared, not restored: their deadlines have likely passed, and the
+    /// claim timer would redraw them the moment they're back.
+    fn restore_pipe(&self, current: &Self, keys: &RedisKeys) -> redis::Pipeline {
+        let mut wins = counted_wins(&self.winners);
+        for (reference, count) in counted_wins(&current.winners) {
+            *wins.entry(reference).or_default() -= count;
+        }
+
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .del(&[
+                &keys.loaded,
+                &keys.raffle,
+                &keys.entries,
+                &keys.winners,
+                &keys.draw_count,
+                &keys.claims,
+                &keys.claim_deadlines,
+                &keys.skipped,
+                &keys.excluded,
+            ])
+            .ignore();
+        if !self.loaded.is_empty() {
+            pipe.sadd(&keys.loaded, &self.loaded).ignore();
+        }
+        if !self.raffle.is_empty() {
+            pipe.rpush(&keys.raffle, &self.raffle).ignore();
+        }
+        if !self.entries.is_empty() {
+            let entries: Vec<(&String, &String)> = self.entries.iter().collect();
+            pipe.hset_multiple(&keys.entries, &entries).ignore();
+        }
+        if !self.winners.is_empty() {
+            pipe.rpush(&keys.winners, &self.winners).ignore();
+        }
+        if let Some(draw_count) = self.draw_count {
+            pipe.set(&keys.draw_count, draw_count).ignore();
+        }
+        if !self.skipped.is_empty() {
+            pipe.rpush(&keys.skipped, &self.skipped).ignore();
+        }
+        if !self.excluded.is_empty() {
+            pipe.sadd(&keys.excluded, &self.excluded).ignore();
+        }
+        for (reference, change) in wins.iter().filter(|(_, change)| **change != 0) {
+            pipe.hincr(&keys.wins, reference, *change).ignore();
+        }
+
+        pipe
+    }
+}
+
+/// Wins that count towards the win cap, by ticket reference
+fn counted_wins(winners: &[String]) -> HashMap<String, i64> {
+    let mut wins = HashMap::new();
+    for winner in winners
+        .iter()
+        .filter_map(|winner| serde_json::from_str::<Winner>(winner).ok())
+        .filter(|winner| winner.status != WinnerStatus::Returned)
+    {
+        if let Some(reference) = winner.ticket_reference {
+            *wins.entry(reference).or_default() += 1;
+        }
+    }
+
+    wins
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub id: u64,
+    pub taken_at: DateTime<Utc>,
+    pub trigger: Trigger,
+    pub state: RaffleState,
+}
+
+/// What restoring a snapshot would change
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Diff {
+    /// Names that come back into the raffle
+    pub back: Vec<String>,
+    /// Names taken out of the raffle
+    pub gone: Vec<String>,
+    /// Winners now and after restoring
+    pub winners: (usize, usize),
+    /// Exclusions now and after restoring
+    pub excluded: (usize, usize),
+}
+
+impl Diff {
+    pub fn new(current: &RaffleState, snapshot: &RaffleState) -> Self {
+        Self {
+            back: missing(&snapshot.raffle, &current.raffle),
+            gone: missing(&current.raffle, &snapshot.raffle),
+            winners: (current.winners.len(), snapshot.winners.len()),
+            excluded: (current.excluded.len(), snapshot.excluded.len()),
+        }
+    }
+
+    pub fn text(&self, locale: Locale) -> Vec<String> {
+        let names = |names: &[String]| {
+            let mut listed = names
+                .iter()
+                .take(PREVIEW_NAMES)
+                .map(String::as_str)
+                .collect::<Vec<&str>>()
+                .join(", ");
+            if names.len() > PREVIEW_NAMES {
+                listed.push_str(&format!(", +{}", names.len() - PREVIEW_NAMES));
+            }
+            listed
+        };
+
+        let mut lines = Vec::new();
+        if !self.back.is_empty() {
+            lines.push(
+                Msg::EntriesBack {
+                    count: self.back.len(),
+                    names: &names(&self.back),
+                }
+                .text(locale),
+            );
+        }
+        if !self.gone.is_empty() {
+            lines.push(
+                Msg::EntriesGone {
+                    count: self.gone.len(),
+                    names: &names(&self.gone),
+                }
+                .text(locale),
+            );
+        }
+        let (now, then) = self.winners;
+        lines.push(Msg::WinnersChange { now, then }.text(locale));
+        if self.excluded.0 != self.excluded.1 {
+            let (now, then) = self.excluded;
+            lines.push(Msg::ExcludedChange { now, then }.text(locale));
+        }
+
+        lines
+    }
+}
+
+/// Names in `from` that `other` has fewer of, sorted
+fn missing(from: &[String], other: &[String]) -> Vec<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for name in other {
+        *counts.entry(name).or_default() += 1;
+    }
+
+    let mut missing: Vec<String> = from
+        .iter()
+        .filter(|name| match counts.get_mut(name.as_str()) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect();
+    missing.sort();
+    missing
+}
+
+fn directory(config: &SnapshotConfig, keys: &RedisKeys) -> PathBuf {
+    config.directory.join(&keys.snapshots)
+}
+
+/// Snapshot ids kept, oldest first
+async fn ids(
+    config: &SnapshotConfig,
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+) -> io::Result<Vec<u64>> {
+    let mut ids: Vec<u64> = match config.store {
+        SnapshotStore::Redis => {
+            let mut redis_connection = redis_pool.get().await.unwrap();
+            redis_connection.hkeys(&keys.snapshots).await.unwrap()
+        }
+        SnapshotStore::Disk => {
+            let mut ids = Vec::new();
+            let mut files = match tokio::fs::read_dir(directory(config, keys)).await {
+                Ok(files) => files,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(ids),
+                Err(err) => return Err(err),
+            };
+            while let Some(file) = files.next_entry().await? {
+                if let Some(id) = file
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.strip_suffix(".json"))
+                    .and_then(|id| id.parse().ok())
+                {
+                    ids.push(id);
+                }
+            }
+            ids
+        }
+    };
+    ids.sort_unstable();
+
+    Ok(ids)
+}
+
+pub async fn get(
+    config: &SnapshotConfig,
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    id: u64,
+) -> io::Result<Option<Snapshot>> {
+    let snapshot: Option<String> = match config.store {
+        SnapshotStore::Redis => {
+            let mut redis_connection = redis_pool.get().await.unwrap();
+            redis_connection.hget(&keys.snapshots, id).await.unwrap()
+        }
+        SnapshotStore::Disk => {
+            match tokio::fs::read_to_string(directory(config, keys).join(format!("{id}.json")))
+                .await
+            {
+                Ok(snapshot) => Some(snapshot),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+                Err(err) => return Err(err),
+            }
+        }
+    };
+
+    Ok(snapshot.and_then(|snapshot| serde_json::from_str(&snapshot).ok()))
+}
+
+/// Every snapshot kept, oldest first
+pub async fn list(
+    config: &SnapshotConfig,
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+) -> io::Result<Vec<Snapshot>> {
+    let mut snapshots = Vec::new();
+    for id in ids(config, redis_pool, keys).await? {
+        if let Some(snapshot) = get(config, redis_pool, keys, id).await? {
+            snapshots.push(snapshot);
+        }
+    }
+
+    Ok(snapshots)
+}
+
+/// Save the raffle as it is now, then delete snapshots past `keep`
+pub async fn take(
+    config: &SnapshotConfig,
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    trigger: Trigger,
+) -> io::Result<Snapshot> {
+    let state = RaffleState::read(redis_pool, keys).await.unwrap();
+    let ids = ids(config, redis_pool, keys).await?;
+    let id = match config.store {
+        SnapshotStore::Redis => {
+            let mut redis_connection = redis_pool.get().await.unwrap();
+            redis_connection
+                .incr(&keys.snapshot_count, 1)
+                .await
+                .unwrap()
+        }
+        SnapshotStore::Disk => ids.last().map_or(1, |id| id + 1),
+    };
+    let snapshot = Snapshot {
+        id,
+        taken_at: Utc::now(),
+        trigger,
+        state,
+    };
+    let json = serde_json::to_string(&snapshot).unwrap();
+
+    let stale = ids.len().saturating_sub(config.keep.saturating_sub(1));
+    match config.store {
+        SnapshotStore::Redis => {
+            let mut redis_connection = redis_pool.get().await.unwrap();
+            let _: () = redis_connection
+                .hset(&keys.snapshots, id, json)
+                .await
+                .unwrap();
+            if stale > 0 {
+                let _: () = redis_connection
+                    .hdel(&keys.snapshots, &ids[..stale])
+                    .await
+                    .unwrap();
+            }
+        }
+        SnapshotStore::Disk => {
+            let directory = directory(config, keys);
+            tokio::fs::create_dir_all(&directory).await?;
+            tokio::fs::write(directory.join(format!("{id}.json")), json).await?;
+            for id in &ids[..stale] {
+                tokio::fs::remove_file(directory.join(format!("{id}.json"))).await?;
+            }
+        }
+    }
+    info!("Took snapshot #{} of {} ({:?})", id, keys.raffle, trigger);
+
+    Ok(snapshot)
+}
+
+/// Snapshot before a destructive command. Returns the refusal to send when
+/// that fails, so nothing is changed without a way back.
+pub async fn before(
+    ctx: &Context,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    trigger: Trigger,
+) -> Option<Msg<'static>> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    match take(&guild.snapshots, &redis_pool, keys, trigger).await {
+        Ok(_) => None,
+        Err(err) => {
+            error!("Cannot take a snapshot: {}", err);
+            Some(Msg::SnapshotFailed)
+        }
+    }
+}
+
+/// Take timed snapshots in the background. Only the first call starts
+/// anything, since `ready` fires again on every reconnect.
+pub fn watch(ctx: Context) {
+    if WATCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            let shutdown = type_map_keys::Shutdown::get(&ctx.data).await;
+            let Some(_in_flight) = shutdown.track() else {
+                break;
+            };
+
+            let config = type_map_keys::Config::get(&ctx.data).await;
+            for guild in config.guilds.values() {
+                if let Err(err) = timed(&ctx, guild).await {
+                    error!("Cannot snapshot guild {}: {}", guild.guild_id, err);
+                }
+            }
+        }
+    });
+}
+
+/// Snapshot when the interval passed since the last one and something changed
+async fn timed(ctx: &Context, guild: &GuildConfig) -> io::Result<()> {
+    let config = &guild.snapshots;
+    if config.interval_minutes == 0 {
+        return Ok(());
+    }
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let event = events::current(&redis_pool, guild).await;
+    if event.archived {
+        return Ok(());
+    }
+    let keys = event.keys(guild);
+
+    let latest = match ids(config, &redis_pool, &keys).await?.last() {
+        Some(id) => get(config, &redis_pool, &keys, *id).await?,
+        None => None,
+    };
+    if let Some(latest) = latest {
+        let interval = ChronoDuration::minutes(config.interval_minutes as i64);
+        if Utc::now() - latest.taken_at < interval
+            || RaffleState::read(&redis_pool, &keys).await.unwrap() == latest.state
+        {
+            return Ok(());
+        }
+    }
+
+    take(config, &redis_pool, &keys, Trigger::Timer).await?;
+    Ok(())
+}
+
+async fn respond(
+    ctx: &Context,
+    command: &CommandInteraction,
+    message: CreateInteractionResponseMessage,
+) -> serenity::Result<()> {
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(message.ephemeral(true)),
+        )
+        .await
+}
+
+#[instrument(skip(ctx))]
+pub async fn snapshots(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let content = match list(&guild.snapshots, &redis_pool, keys).await {
+        Ok(snapshots) if snapshots.is_empty() => Msg::NoSnapshots.text(locale),
+        Ok(snapshots) => {
+            let mut lines: Vec<String> = snapshots
+                .iter()
+                .rev()
+                .take(LISTED_SNAPSHOTS)
+                .map(|snapshot| {
+                    Msg::SnapshotLine {
+                        id: snapshot.id,
+                        at: &schedule::local_time(snapshot.taken_at, guild.time_zone),
+                        trigger: snapshot.trigger,
+                        entries: snapshot.state.raffle.len(),
+                        winners: snapshot.state.winners.len(),
+                    }
+                    .text(locale)
+                })
+                .collect();
+            if snapshots.len() > LISTED_SNAPSHOTS {
+                lines.push(Msg::AndMore(snapshots.len() - LISTED_SNAPSHOTS).text(locale));
+            }
+            lines.join("\n")
+        }
+        Err(err) => {
+            error!("Cannot list snapshots: {}", err);
+            Msg::SnapshotFailed.text(locale)
+        }
+    };
+
+    respond(
+        ctx,
+        command,
+        CreateInteractionResponseMessage::new().content(content),
+    )
+    .await
+}
+
+/// Preview what restoring would change, with buttons to go ahead or not
+#[instrument(skip(ctx))]
+pub async fn restore(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    id: u64,
+    locale: Locale,
+) -> serenity::Result<()> {
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let snapshot = match get(&guild.snapshots, &redis_pool, keys, id).await {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => {
+            let message = CreateInteractionResponseMessage::new()
+                .content(Msg::UnknownSnapshot(id).text(locale));
+            return respond(ctx, command, message).await;
+        }
+        Err(err) => {
+            error!("Cannot read snapshot {}: {}", id, err);
+            let message =
+                CreateInteractionResponseMessage::new().content(Msg::SnapshotFailed.text(locale));
+            return respond(ctx, command, message).await;
+        }
+    };
+    let current = RaffleState::read(&redis_pool, keys).await.unwrap();
+    if current == snapshot.state {
+        let message = CreateInteractionResponseMessage::new()
+            .content(Msg::SnapshotUnchanged(id).text(locale));
+        return respond(ctx, command, message).await;
+    }
+
+    let mut lines = vec![Msg::RestorePreview {
+        id,
+        at: &schedule::local_time(snapshot.taken_at, guild.time_zone),
+        trigger: snapshot.trigger,
+    }
+    .text(locale)];
+    lines.extend(Diff::new(&current, &snapshot.state).text(locale));
+    let message = CreateInteractionResponseMessage::new()
+        .content(lines.join("\n"))
+        .components(vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(format!("{RESTORE_BUTTON_PREFIX}{id}"))
+                .label(Msg::RestoreButton.text(locale))
+                .style(ButtonStyle::Danger),
+            CreateButton::new(format!("{RESTORE_BUTTON_PREFIX}{CANCEL}"))
+                .label(Msg::CancelButton.text(locale))
+                .style(ButtonStyle::Secondary),
+        ])]);
+
+    respond(ctx, command, message).await
+}
+
+/// Restore or cancel from the preview's buttons. The raffle as it was is
+/// snapshotted first, so a restore can be undone too.
+#[instrument(skip(ctx))]
+pub async fn restore_button(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+) -> serenity::Result<()> {
+    let locale = Locale::resolve(guild.locale, &component.locale);
+    let choice = component
+        .data
+        .custom_id
+        .strip_prefix(RESTORE_BUTTON_PREFIX)
+        .unwrap_or(CANCEL);
+
+    let content = match choice.parse::<u64>() {
+        Err(_) => Msg::RestoreCancelled.text(locale),
+        Ok(id) => {
+            let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+            let snapshot = get(&guild.snapshots, &redis_pool, keys, id).await;
+            match snapshot {
+                Ok(Some(snapshot)) => {
+                    match take(&guild.snapshots, &redis_pool, keys, Trigger::Restore).await {
+                        Ok(backup) => {
+                            snapshot.state.write(&redis_pool, keys).await.unwrap();
+                            info!("Restored snapshot #{} of {}", id, keys.raffle);
+                            Msg::Restored {
+                                id,
+                                backup: backup.id,
+                            }
+                            .text(locale)
+                        }
+                        Err(err) => {
+                            error!("Cannot take a snapshot: {}", err);
+                            Msg::SnapshotFailed.text(locale)
+                        }
+                    }
+                }
+                Ok(None) => Msg::UnknownSnapshot(id).text(locale),
+                Err(err) => {
+                    error!("Cannot read snapshot {}: {}", id, err);
+                    Msg::SnapshotFailed.text(locale)
+                }
+            }
+        }
+    };
+
+    component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(Vec::new()),
+            ),
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_counts_duplicate_names() {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        let current = RaffleState {
+            raffle: names(&["Ada", "Grace"]),
+            winners: names(&["{}"]),
+            ..Default::default()
+        };
+        let snapshot = RaffleState {
+            raffle: names(&["Linus", "Ada", "Ada", "Grace"]),
+            ..Default::default()
+        };
+
+        let diff = Diff::new(&current, &snapshot);
+        assert_eq!(diff.back, vec!["Ada".to_string(), "Linus".to_string()]);
+        assert!(diff.gone.is_empty());
+        assert_eq!(diff.winners, (1, 0));
+        assert_eq!(
+            Diff::new(&snapshot, &current).gone,
+            vec!["Ada".to_string(), "Linus".to_string()]
+        );
+    }
+
+    #[test]
+    fn restore_leaves_out_claims() {
+        let guild: GuildConfig = serde_json::from_str(
+            r#"{"guild_id": 1, "channel_id": 2, "checkin_list_slug": "chk", "redis_namespace": ""}"#,
+        )
+        .unwrap();
+        let keys = RedisKeys::new(&guild, "cosmico");
+        let snapshot = RaffleState {
+            raffle: vec!["Ada".into()],
+            claims: BTreeMap::from([(
+                "1".to_string(),
+                r#"{"channel_id":"2","message_id":"3"}"#.to_string(),
+            )]),
+            // long past, the timer would redraw it at once
+            claim_deadlines: vec![("1".into(), 1_000)],
+            ..Default::default()
+        };
+
+        let packed = snapshot
+            .restore_pipe(&RaffleState::default(), &keys)
+            .get_packed_pipeline();
+        let packed = String::from_utf8_lossy(&packed);
+        assert!(packed.contains("RPUSH"));
+        assert!(!packed.contains("HMSET"));
+        assert!(!packed.contains("ZADD"));
+        assert!(!packed.contains("message_id"));
+    }
+}
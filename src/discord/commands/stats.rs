@@ -0,0 +1,303 @@
+//! `/raffle stats`, numbers about the raffle for organizers and sponsors
+use super::{
+    records::{self, Entry, EntrySource, Winner, WinnerStatus},
+    RedisKeys,
+};
+use crate::{
+    config::GuildConfig,
+    discord::type_map_keys,
+    i18n::{Locale, Msg},
+    metrics,
+    tito::checkin::client::{checkin_lists_handler::Checkin, Client},
+};
+use bb8_redis::redis::AsyncCommands;
+use chrono::{offset::Utc, DateTime, Duration, DurationRound};
+use chrono_tz::Tz;
+use serde::Serialize;
+use serenity::{
+    builder::{CreateEmbed, EditInteractionResponse},
+    client::Context,
+    model::application::CommandInteraction,
+};
+use std::collections::BTreeMap;
+use tracing::{error, instrument};
+
+/// Most bars in the check-in histogram. Hours are merged to fit.
+const HISTOGRAM_BARS: i64 = 24;
+const HISTOGRAM_WIDTH: usize = 20;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Stats {
+    /// Entries left in the raffle
+    pub entries: usize,
+    /// Entries left by release title
+    pub by_release: BTreeMap<String, usize>,
+    /// Entries left without a release title, added by name
+    pub no_release: usize,
+    /// Entries left by how they got in
+    pub by_source: BTreeMap<EntrySource, usize>,
+    /// Names ever added to the raffle
+    pub loaded: usize,
+    /// Tickets with a check-in that wasn't undone
+    pub checked_in: usize,
+    /// Winners drawn, not counting those returned to the raffle
+    pub winners: usize,
+    pub claimed: usize,
+    /// Check-ins by the hour they happened in
+    pub checkins_by_hour: BTreeMap<DateTime<Utc>, usize>,
+}
+
+impl Stats {
+    pub fn new(entries: &[Entry], loaded: usize, winners: &[Winner], checkins: &[Checkin]) -> Self {
+        let mut stats = Self {
+            entries: entries.len(),
+            loaded,
+            ..Default::default()
+        };
+        for entry in entries {
+            match &entry.release_title {
+                Some(release_title) => {
+                    *stats.by_release.entry(release_title.clone()).or_default() += 1
+                }
+                None => stats.no_release += 1,
+            }
+            *stats.by_source.entry(entry.source).or_default() += 1;
+        }
+
+        for winner in winners {
+            match winner.status {
+                WinnerStatus::Returned => {}
+                WinnerStatus::Claimed => {
+                    stats.winners += 1;
+                    stats.claimed += 1;
+                }
+                _ => stats.winners += 1,
+            }
+        }
+
+        let mut tickets = Vec::new();
+        for checkin in checkins
+            .iter()
+            .filter(|checkin| checkin.deleted_at.is_none())
+        {
+            tickets.push(checkin.ticket_id);
+            let hour = checkin
+                .created_at
+                .duration_trunc(Duration::hours(1))
+                .unwrap_or(checkin.created_at);
+            *stats.checkins_by_hour.entry(hour).or_default() += 1;
+        }
+        tickets.sort_unstable();
+        tickets.dedup();
+        stats.checked_in = tickets.len();
+
+        stats
+    }
+
+    /// Check-ins per bar of the histogram, labelled with the local time the
+    /// bar starts at
+    pub fn histogram(&self, tz: Tz) -> Vec<(String, usize)> {
+        let (Some(first), Some(last)) = (
+            self.checkins_by_hour.keys().next(),
+            self.checkins_by_hour.keys().next_back(),
+        ) else {
+            return Vec::new();
+        };
+        let hours = (*last - *first).num_hours() + 1;
+        let hours_per_bar = (hours + HISTOGRAM_BARS - 1) / HISTOGRAM_BARS;
+        let format = if (*last - *first).num_hours() < 24 {
+            "%H:%M"
+        } else {
+            "%m-%d %H:%M"
+        };
+
+        let mut bars: BTreeMap<i64, usize> = BTreeMap::new();
+        for (hour, count) in &self.checkins_by_hour {
+            *bars
+                .entry((*hour - *first).num_hours() / hours_per_bar)
+                .or_default() += count;
+        }
+        (0..(hours + hours_per_bar - 1) / hours_per_bar)
+            .map(|bar| {
+                let start = *first + Duration::hours(bar * hours_per_bar);
+                let label = start.with_timezone(&tz).format(format).to_string();
+                (label, bars.get(&bar).copied().unwrap_or_default())
+            })
+            .collect()
+    }
+
+    /// Titles and text of each section
+    pub fn fields(&self, tz: Tz, locale: Locale) -> Vec<(String, String)> {
+        let mut releases: Vec<String> = self
+            .by_release
+            .iter()
+            .map(|(release_title, count)| format!("{release_title}: {count}"))
+            .collect();
+        if self.no_release > 0 {
+            releases.push(format!(
+                "{}: {}",
+                Msg::NoRelease.text(locale),
+                self.no_release
+            ));
+        }
+        let sources: Vec<String> = self
+            .by_source
+            .iter()
+            .map(|(source, count)| format!("{}: {count}", Msg::Source(*source).text(locale)))
+            .collect();
+
+        let histogram = self.histogram(tz);
+        let most = histogram.iter().map(|(_, count)| *count).max().unwrap_or(1);
+        let bars: Vec<String> = histogram
+            .iter()
+            .map(|(label, count)| {
+                let width = (count * HISTOGRAM_WIDTH).div_ceil(most);
+                format!("{label} {} {count}", "█".repeat(width))
+            })
+            .collect();
+
+        let or_none = |lines: Vec<String>| {
+            if lines.is_empty() {
+                "-".to_string()
+            } else {
+                lines.join("\n")
+            }
+        };
+        vec![
+            (
+                Msg::StatsEntries.text(locale),
+                Msg::RaffleSize(self.entries).text(locale),
+            ),
+            (Msg::StatsReleases.text(locale), or_none(releases)),
+            (Msg::StatsSources.text(locale), or_none(sources)),
+            (
+                Msg::StatsCheckedIn.text(locale),
+                Msg::CheckedInCounts {
+                    checked_in: self.checked_in,
+                    loaded: self.loaded,
+                }
+                .text(locale),
+            ),
+            (
+                Msg::StatsWinners.text(locale),
+                Msg::WinnerCounts {
+                    winners: self.winners,
+                    claimed: self.claimed,
+                }
+                .text(locale),
+            ),
+            (
+                Msg::StatsCheckinTimes.text(locale),
+                if bars.is_empty() {
+                    "-".to_string()
+                } else {
+                    format!("```\n{}\n```", bars.join("\n"))
+                },
+            ),
+        ]
+    }
+
+    pub fn embed(&self, tz: Tz, locale: Locale) -> CreateEmbed {
+        let fields = self.fields(tz, locale);
+        let inline = fields.len() - 1;
+        CreateEmbed::new()
+            .title(Msg::StatsTitle.text(locale))
+            .fields(
+                fields
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, (name, value))| (name, value, index < inline)),
+            )
+    }
+}
+
+/// Gather [`Stats`] from Redis and the check-in list
+pub async fn stats(
+    tito_client: &Client,
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+    checkin_list_slug: &str,
+) -> Result<Stats, reqwest::Error> {
+    let checkins = metrics::time_tito(
+        "checkins",
+        tito_client.check_ins(checkin_list_slug).checkins().send(),
+    )
+    .await?;
+    let entries = records::entries(redis_pool, keys).await.unwrap();
+    let winners = records::winners(redis_pool, keys).await.unwrap();
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    let loaded: usize = redis_connection.scard(&keys.loaded).await.unwrap();
+
+    Ok(Stats::new(&entries, loaded, &winners, &checkins))
+}
+
+#[instrument(skip(ctx))]
+pub async fn show(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    locale: Locale,
+) -> serenity::Result<()> {
+    // check-ins come from Tito, which can outlast the interaction deadline
+    command.defer(&ctx.http).await?;
+    let tito_client = type_map_keys::TitoClient::get(&ctx.data).await;
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+
+    let response = match stats(&tito_client, &redis_pool, keys, &guild.checkin_list_slug).await {
+        Ok(stats) => EditInteractionResponse::new().embed(stats.embed(guild.time_zone, locale)),
+        Err(err) => {
+            error!("Cannot fetch check-ins for stats: {}", err);
+            EditInteractionResponse::new().content(Msg::TitoUnavailable.text(locale))
+        }
+    };
+
+    command.edit_response(&ctx.http, response).await.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn checkin(ticket_id: u32, hour: u32, minute: u32) -> Checkin {
+        let at = Utc.with_ymd_and_hms(2024, 5, 4, hour, minute, 0).unwrap();
+        Checkin {
+            id: ticket_id,
+            uuid: String::new(),
+            ticket_id,
+            created_at: at,
+            updated_at: at,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn counts_entries_and_checkins() {
+        let entries = vec![
+            Entry::new("Ada", EntrySource::Tito).release_title("General"),
+            Entry::new("Grace", EntrySource::Tito).release_title("General"),
+            Entry::new("Linus", EntrySource::Manual),
+        ];
+        let checkins = vec![checkin(1, 9, 5), checkin(1, 9, 30), checkin(2, 12, 59)];
+
+        let stats = Stats::new(&entries, 4, &[], &checkins);
+        assert_eq!(stats.by_release.get("General"), Some(&2));
+        assert_eq!(stats.no_release, 1);
+        assert_eq!(stats.by_source.get(&EntrySource::Manual), Some(&1));
+        assert_eq!(stats.checked_in, 2);
+        // maps keyed by source and hour still make JSON for the CLI
+        assert!(serde_json::to_value(&stats).is_ok());
+
+        let histogram = stats.histogram(Tz::UTC);
+        assert_eq!(
+            histogram,
+            vec![
+                ("09:00".to_string(), 2),
+                ("10:00".to_string(), 0),
+                ("11:00".to_string(), 0),
+                ("12:00".to_string(), 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! How many times one ticket may win across the raffles of an event. Tickets
+//! are counted by reference, so entries without one are never capped.
+use super::{records::Entry, RedisKeys};
+use bb8_redis::redis::{self, AsyncCommands};
+use std::collections::HashMap;
+
+/// Wins by ticket reference, across the event
+pub async fn wins(
+    redis_pool: &bb8::Pool<bb8_redis::RedisConnectionManager>,
+    keys: &RedisKeys,
+) -> redis::RedisResult<HashMap<String, u32>> {
+    let mut redis_connection = redis_pool.get().await.unwrap();
+    redis_connection.hgetall(&keys.wins).await
+}
+
+/// Wins of an entry's ticket, when they reached `cap`
+pub fn at_cap(wins: &HashMap<String, u32>, cap: Option<u32>, entry: &Entry) -> Option<u32> {
+    let wins = *wins.get(entry.ticket_reference.as_deref()?)?;
+    (wins >= cap?).then_some(wins)
+}
@@ -1,39 +1,32 @@
 //! Collection of Serenity TypeMapKeys
-use crate::tito::checkin::client::Client;
+use crate::{
+    config,
+    discord::commands::rng::DrawRng,
+    shutdown,
+    tito::{
+        admin,
+        checkin::{client::Client, ticket_cache},
+    },
+};
 use bb8_redis::RedisConnectionManager;
-use rand::Rng as Rand;
 use serenity::{
-    model::{
-        id::{ChannelId as SerenityChannelId, UserId as SerenityUserId},
-        prelude::GuildId as SerenityGuildId,
-    },
+    model::id::UserId as SerenityUserId,
     prelude::{RwLock, TypeMap, TypeMapKey},
 };
 use std::sync::Arc;
 
-pub struct ChannelId;
+pub struct Config;
 
-impl TypeMapKey for ChannelId {
-    type Value = SerenityChannelId;
+impl TypeMapKey for Config {
+    type Value = Arc<config::Config>;
 }
 
-impl ChannelId {
-    pub async fn get(data: &Arc<RwLock<TypeMap>>) -> SerenityChannelId {
+impl Config {
+    pub async fn get(data: &Arc<RwLock<TypeMap>>) -> Arc<config::Config> {
         let data = data.read().await;
-        *data.get::<Self>().expect("Expected ChannelId in TypeMap")
-    }
-}
-
-pub struct GuildId;
-
-impl TypeMapKey for GuildId {
-    type Value = SerenityGuildId;
-}
-
-impl GuildId {
-    pub async fn get(data: &Arc<RwLock<TypeMap>>) -> SerenityGuildId {
-        let data = data.read().await;
-        *data.get::<Self>().expect("Expected GuildId in TypeMap")
+        data.get::<Self>()
+            .expect("Expected Config in TypeMap")
+            .clone()
     }
 }
 
@@ -78,31 +71,56 @@ impl TitoClient {
     }
 }
 
+/// Randomness for live draws
 pub struct Rng;
 impl TypeMapKey for Rng {
-    type Value = Arc<RwLock<rand::rngs::StdRng>>;
+    type Value = Arc<RwLock<DrawRng>>;
 }
 
 impl Rng {
-    pub async fn rand(data: &Arc<RwLock<TypeMap>>, max: usize) -> usize {
+    pub async fn get(data: &Arc<RwLock<TypeMap>>) -> Arc<RwLock<DrawRng>> {
         let data = data.read().await;
-        let rng_lock = data.get::<Rng>().expect("Expected Rng in TypeMap");
+        data.get::<Self>().expect("Expected Rng in TypeMap").clone()
+    }
+}
+
+pub struct Shutdown;
+impl TypeMapKey for Shutdown {
+    type Value = Arc<shutdown::Shutdown>;
+}
 
-        let mut rng = rng_lock.write().await;
-        rng.gen_range(0..max)
+impl Shutdown {
+    pub async fn get(data: &Arc<RwLock<TypeMap>>) -> Arc<shutdown::Shutdown> {
+        let data = data.read().await;
+        data.get::<Self>()
+            .expect("Expected Shutdown in TypeMap")
+            .clone()
     }
 }
 
-pub struct CheckinListSlug;
-impl TypeMapKey for CheckinListSlug {
-    type Value = String;
+pub struct TicketCache;
+impl TypeMapKey for TicketCache {
+    type Value = Arc<ticket_cache::TicketCache>;
 }
 
-impl CheckinListSlug {
-    pub async fn get(data: &Arc<RwLock<TypeMap>>) -> String {
+impl TicketCache {
+    pub async fn get(data: &Arc<RwLock<TypeMap>>) -> Arc<ticket_cache::TicketCache> {
         let data = data.read().await;
-        data.get::<CheckinListSlug>()
-            .expect("Expected StringValue in TypeMap")
+        data.get::<Self>()
+            .expect("Expected TicketCache in TypeMap")
             .clone()
     }
 }
+
+/// Admin API client, only present when `TITO_API_TOKEN` is set
+pub struct TitoAdminClient;
+impl TypeMapKey for TitoAdminClient {
+    type Value = admin::client::Client;
+}
+
+impl TitoAdminClient {
+    pub async fn get(data: &Arc<RwLock<TypeMap>>) -> Option<admin::client::Client> {
+        let data = data.read().await;
+        data.get::<Self>().cloned()
+    }
+}
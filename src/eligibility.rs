@@ -0,0 +1,153 @@
+//! Who may be in the raffle, as [`Rule`]s every entry has to pass
+use crate::tito::checkin::client::checkin_lists_handler::Ticket;
+use chrono::{offset::Utc, DateTime};
+use serde::Deserialize;
+
+/// One condition on an entry, configured per guild under `eligibility`
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum Rule {
+    /// The ticket's release title is one of these
+    ReleaseIn {
+        titles: Vec<String>,
+    },
+    /// Checked in, optionally within a window
+    CheckedIn {
+        #[serde(default)]
+        after: Option<DateTime<Utc>>,
+        #[serde(default)]
+        before: Option<DateTime<Utc>>,
+    },
+    /// First and last name are filled in
+    Named,
+    TagPresent {
+        tag: String,
+    },
+    TagAbsent {
+        tag: String,
+    },
+    /// Not drawn before, unless they were returned to the raffle
+    NotPreviousWinner,
+    /// Answered the registration question with this slug
+    QuestionAnswered {
+        question: String,
+    },
+}
+
+/// What the rules know about one entry
+#[derive(Debug, Default)]
+pub struct Facts<'a> {
+    /// Entries added by a name that's on no ticket only face the rules that
+    /// don't need one
+    pub ticket: Option<&'a Ticket>,
+    /// Earliest check-in that wasn't undone
+    pub checked_in_at: Option<DateTime<Utc>>,
+    /// Tags from the check-in list and the admin API
+    pub tags: Vec<&'a str>,
+    pub previous_winner: bool,
+    /// Slugs of the questions answered
+    pub answered: Vec<&'a str>,
+}
+
+impl Rule {
+    pub fn allows(&self, facts: &Facts) -> bool {
+        match (self, facts.ticket) {
+            (Self::NotPreviousWinner, _) => !facts.previous_winner,
+            (_, None) => true,
+            (rule, Some(ticket)) => rule.allows_ticket(ticket, facts),
+        }
+    }
+
+    fn allows_ticket(&self, ticket: &Ticket, facts: &Facts) -> bool {
+        let tagged = |tag: &str| facts.tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+        match self {
+            Self::ReleaseIn { titles } => titles.contains(&ticket.release_title),
+            Self::CheckedIn { after, before } => facts.checked_in_at.is_some_and(|at| {
+                after.is_none_or(|after| at >= after) && before.is_none_or(|before| at < before)
+            }),
+            Self::Named => ticket.full_name().is_some(),
+            Self::TagPresent { tag } => tagged(tag),
+            Self::TagAbsent { tag } => !tagged(tag),
+            Self::NotPreviousWinner => !facts.previous_winner,
+            Self::QuestionAnswered { question } => facts.answered.contains(&question.as_str()),
+        }
+    }
+}
+
+/// Rules every entry has to pass
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rules(Vec<Rule>);
+
+impl Rules {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self(rules)
+    }
+
+    /// The first rule an entry breaks
+    pub fn broken(&self, facts: &Facts) -> Option<&Rule> {
+        self.0.iter().find(|rule| !rule.allows(facts))
+    }
+
+    /// Whether any rule looks at tags, which the admin API knows more of
+    pub fn needs_tags(&self) -> bool {
+        self.0
+            .iter()
+            .any(|rule| matches!(rule, Rule::TagPresent { .. } | Rule::TagAbsent { .. }))
+    }
+
+    /// Slugs of the questions the rules look at
+    pub fn questions(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().filter_map(|rule| match rule {
+            Rule::QuestionAnswered { question } => Some(question.as_str()),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn rules_check_facts() {
+        let ticket: Ticket =
+            serde_json::from_str(include_str!("../fixtures/checkin/ticket.json")).unwrap();
+        let rules: Vec<Rule> = serde_json::from_str(
+            r#"[
+                {"rule": "release_in", "titles": ["Con of Heroes Early Bird Ticket"]},
+                {"rule": "checked_in", "before": "2024-05-04T18:00:00Z"},
+                {"rule": "tag_absent", "tag": "staff"},
+                {"rule": "not_previous_winner"}
+            ]"#,
+        )
+        .unwrap();
+        let rules = Rules::new(rules);
+
+        let mut facts = Facts {
+            ticket: Some(&ticket),
+            checked_in_at: Some(Utc.with_ymd_and_hms(2024, 5, 4, 12, 0, 0).unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(rules.broken(&facts), None);
+
+        facts.tags = vec!["Staff"];
+        assert_eq!(
+            rules.broken(&facts),
+            Some(&Rule::TagAbsent {
+                tag: "staff".into()
+            })
+        );
+
+        facts.tags.clear();
+        facts.checked_in_at = Some(Utc.with_ymd_and_hms(2024, 5, 4, 19, 0, 0).unwrap());
+        assert!(matches!(rules.broken(&facts), Some(Rule::CheckedIn { .. })));
+
+        // a name with no ticket only faces the winner rule
+        let by_name = Facts {
+            previous_winner: true,
+            ..Default::default()
+        };
+        assert_eq!(rules.broken(&by_name), Some(&Rule::NotPreviousWinner));
+    }
+}
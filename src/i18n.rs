@@ -0,0 +1,1095 @@
+//! Message catalog for bot responses and command descriptions
+use crate::{
+    discord::commands::{
+        records::{EntrySource, WinnerStatus},
+        snapshots::Trigger,
+    },
+    eligibility::Rule,
+};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, strum::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+}
+
+impl Locale {
+    /// Language of a Discord locale, such as `es-419`
+    pub fn from_discord(locale: &str) -> Option<Self> {
+        match locale.split('-').next() {
+            Some("en") => Some(Self::En),
+            Some("es") => Some(Self::Es),
+            _ => None,
+        }
+    }
+
+    /// The guild's configured locale, otherwise the one of whoever ran the
+    /// command
+    pub fn resolve(configured: Option<Self>, interaction_locale: &str) -> Self {
+        configured
+            .or_else(|| Self::from_discord(interaction_locale))
+            .unwrap_or_default()
+    }
+
+    /// Discord locales to fill localization fields for
+    fn discord_locales(self) -> &'static [&'static str] {
+        match self {
+            Self::En => &["en-US", "en-GB"],
+            Self::Es => &["es-ES", "es-419"],
+        }
+    }
+}
+
+/// A bot response, rendered with [`Msg::text`]
+#[derive(Clone, Debug)]
+pub enum Msg<'a> {
+    Restarting,
+    UnconfiguredGuild,
+    NotOrganizer,
+    Loaded {
+        loaded: &'a str,
+        total: &'a str,
+    },
+    LoadFetched {
+        tickets: &'a str,
+    },
+    LoadFiltered {
+        tickets: &'a str,
+        attendees: &'a str,
+    },
+    Drawing,
+    NoEntries,
+    WinnersTitle {
+        count: usize,
+        prize: Option<&'a str>,
+    },
+    Page {
+        page: usize,
+        pages: usize,
+    },
+    FewerWinners {
+        drawn: usize,
+        amount: u64,
+    },
+    Cleared,
+    Added(&'a str),
+    AlreadyInRaffle(&'a str),
+    NoNamedTicket(&'a str),
+    RaffleSize(usize),
+    TitoUnavailable,
+    UnknownTicket(&'a str),
+    UnnamedTicket(&'a str),
+    IneligibleRelease(&'a str),
+    NotCheckedIn(&'a str),
+    TicketTaken(&'a str),
+    AccountLinked(&'a str),
+    Entered(&'a str),
+    AlreadyWon(&'a str),
+    InvalidScheduleTime(&'a str),
+    ScheduleInPast,
+    Scheduled {
+        id: u64,
+        at: &'a str,
+    },
+    NoSchedules,
+    ScheduleLine {
+        id: u64,
+        at: &'a str,
+        amount: u64,
+        prize: Option<&'a str>,
+        repeat_minutes: Option<u64>,
+    },
+    ScheduleCancelled(u64),
+    UnknownSchedule(u64),
+    Excluded {
+        count: usize,
+        reasons: &'a str,
+    },
+    ExcludedTicket,
+    ExcludedByList,
+    ExcludedTag(&'a str),
+    ExcludedDomain(&'a str),
+    ExcludedEntry {
+        name: &'a str,
+        reason: &'a str,
+    },
+    ExclusionAdded(&'a str),
+    /// Entries already in the raffle that a new exclusion matched
+    ExcludedEntriesDropped(usize),
+    ExclusionRemoved(&'a str),
+    NotExcluded(&'a str),
+    NoExclusions,
+    TicketExcluded(&'a str),
+    Ineligible {
+        name: &'a str,
+        reason: &'a str,
+    },
+    /// Why an entry breaks a rule, to go in [`Msg::Ineligible`]
+    BrokenRule(&'a Rule),
+    Odds {
+        entries: usize,
+        eligible: usize,
+    },
+    NotInRaffle(&'a str),
+    CappedEntry {
+        name: &'a str,
+        wins: u32,
+        cap: u32,
+    },
+    EntryOdds {
+        name: &'a str,
+        entries: usize,
+        percent: f64,
+    },
+    AtWinCap(&'a str),
+    NoHistory,
+    HistoryWin {
+        number: u64,
+        name: &'a str,
+        prize: Option<&'a str>,
+        status: &'a str,
+    },
+    /// A winner's status, to go in [`Msg::HistoryWin`]
+    Status(WinnerStatus),
+    HistorySkip {
+        name: &'a str,
+        wins: u32,
+        cap: u32,
+    },
+    StatsTitle,
+    StatsEntries,
+    StatsReleases,
+    /// Stands in for the release title of entries added by name
+    NoRelease,
+    StatsSources,
+    Source(EntrySource),
+    StatsCheckedIn,
+    CheckedInCounts {
+        checked_in: usize,
+        loaded: usize,
+    },
+    StatsWinners,
+    WinnerCounts {
+        winners: usize,
+        claimed: usize,
+    },
+    StatsCheckinTimes,
+    /// Why a snapshot was taken, to go in [`Msg::SnapshotLine`]
+    Trigger(Trigger),
+    SnapshotFailed,
+    NoSnapshots,
+    SnapshotLine {
+        id: u64,
+        at: &'a str,
+        trigger: Trigger,
+        entries: usize,
+        winners: usize,
+    },
+    UnknownSnapshot(u64),
+    SnapshotUnchanged(u64),
+    RestorePreview {
+        id: u64,
+        at: &'a str,
+        trigger: Trigger,
+    },
+    EntriesBack {
+        count: usize,
+        names: &'a str,
+    },
+    EntriesGone {
+        count: usize,
+        names: &'a str,
+    },
+    WinnersChange {
+        now: usize,
+        then: usize,
+    },
+    ExcludedChange {
+        now: usize,
+        then: usize,
+    },
+    RestoreButton,
+    CancelButton,
+    Restored {
+        id: u64,
+        backup: u64,
+    },
+    RestoreCancelled,
+    InvalidEventId(&'a str),
+    EventStarted {
+        id: &'a str,
+        entries: usize,
+    },
+    /// Schedules of the event switched away from, which won't run
+    SchedulesLeft {
+        count: usize,
+        event: &'a str,
+    },
+    EventArchived(&'a str),
+    Archived {
+        id: &'a str,
+        days: u64,
+    },
+    ArchivedForGood(&'a str),
+    Rehearsal {
+        seed: u64,
+        winners: &'a str,
+    },
+    ReAdding(&'a str),
+    EntriesPage {
+        count: usize,
+        page: usize,
+        pages: usize,
+    },
+    PreviousButton,
+    NextButton,
+    NoMatches(&'a str),
+    Matches {
+        count: usize,
+        text: &'a str,
+    },
+    FirstMatches {
+        shown: usize,
+        count: usize,
+        text: &'a str,
+    },
+    AndMore(usize),
+    /// Autocomplete label of a ticket already in the raffle
+    EnteredChoice {
+        name: &'a str,
+        reference: &'a str,
+    },
+    Removed(&'a str),
+    ExportSummary {
+        entries: usize,
+        winners: usize,
+    },
+    ExportFailed(&'a str),
+    Imported {
+        added: usize,
+        duplicates: usize,
+    },
+    ImportRejected {
+        excluded: usize,
+        ineligible: usize,
+    },
+    UnknownReferences(&'a str),
+    ReadFailed {
+        file: &'a str,
+        error: &'a str,
+    },
+    DownloadFailed {
+        file: &'a str,
+        error: &'a str,
+    },
+    ClaimButton,
+    NoShowButton,
+    /// A settled winner, replacing the mention on their announcement
+    ClaimStatus {
+        status: WinnerStatus,
+        name: &'a str,
+    },
+    AlreadySettled,
+    NoReplacement,
+    /// Stands in for the name of a winner without a record
+    TheWinner,
+    WinnerTitle,
+    NameField,
+    ReleaseField,
+    PrizeField,
+    DrawField,
+    DmsClosed {
+        mention: &'a str,
+        text: &'a str,
+    },
+    ReturnedToRaffle(&'a str),
+    UnknownDraw(u64),
+    NotAnnouncement,
+    SettlePlaceholder,
+    ReturnPlaceholder,
+    NothingToReturn,
+}
+
+impl Msg<'_> {
+    pub fn text(&self, locale: Locale) -> String {
+        match locale {
+            Locale::En => self.en(),
+            Locale::Es => self.es(),
+        }
+    }
+
+    fn en(&self) -> String {
+        match self {
+            Self::Restarting => "The bot is restarting, try again in a minute.".into(),
+            Self::UnconfiguredGuild => "This server is not set up for raffles.".into(),
+            Self::NotOrganizer => "Only raffle organizers can do that.".into(),
+            Self::Loaded { loaded, total } => {
+                format!("Loaded {loaded} users\n{total} total users.")
+            }
+            Self::LoadFetched { tickets } => format!("Fetched {tickets} tickets…"),
+            Self::LoadFiltered { tickets, attendees } => {
+                format!("Fetched {tickets} tickets… filtered to {attendees} new attendees… loading")
+            }
+            Self::Drawing => "🎰 Drawing…".into(),
+            Self::NoEntries => "No entries in the raffle.".into(),
+            Self::WinnersTitle {
+                count,
+                prize: Some(prize),
+            } => format!("🎉 {count} winners of {prize}"),
+            Self::WinnersTitle { count, prize: None } => format!("🎉 {count} winners"),
+            Self::Page { page, pages } => format!("Page {page} of {pages}"),
+            Self::FewerWinners { drawn, amount } => format!(
+                "Only {drawn} of the {amount} winners asked for were drawn, \
+                 no entries are left that can win."
+            ),
+            Self::Cleared => "Cleared list".into(),
+            Self::Added(name) => format!("Added {name}"),
+            Self::AlreadyInRaffle(name) => format!("{name} is already in the raffle"),
+            Self::NoNamedTicket(name) => format!("No named ticket {name} on the check-in list"),
+            Self::RaffleSize(size) => format!("{size} entries in the raffle"),
+            Self::TitoUnavailable => "Could not reach Tito, try again in a minute.".into(),
+            Self::UnknownTicket(reference) => {
+                format!("No ticket {reference} on the check-in list.")
+            }
+            Self::UnnamedTicket(reference) => {
+                format!("Ticket {reference} has no name on it, ask at the registration desk.")
+            }
+            Self::IneligibleRelease(release) => {
+                format!("{release} tickets are not part of the raffle.")
+            }
+            Self::NotCheckedIn(reference) => format!(
+                "Ticket {reference} isn't checked in yet, check in at the registration desk first."
+            ),
+            Self::TicketTaken(reference) => {
+                format!("Ticket {reference} is already linked to another Discord account.")
+            }
+            Self::AccountLinked(reference) => {
+                format!("Your Discord account is already linked to ticket {reference}.")
+            }
+            Self::Entered(name) => format!("You're in the raffle, {name}. Good luck!"),
+            Self::AlreadyWon(name) => {
+                format!("{name} has already won, so can't enter the raffle again.")
+            }
+            Self::InvalidScheduleTime(at) => {
+                format!("Can't read the time {at}, use HH:MM or YYYY-MM-DD HH:MM.")
+            }
+            Self::ScheduleInPast => "That time has already passed.".into(),
+            Self::Scheduled { id, at } => format!("Scheduled draw #{id} for {at}"),
+            Self::NoSchedules => "No scheduled draws.".into(),
+            Self::ScheduleLine {
+                id,
+                at,
+                amount,
+                prize,
+                repeat_minutes,
+            } => {
+                let mut line = format!("#{id} {at}: {amount} winners");
+                if let Some(prize) = prize {
+                    line.push_str(&format!(" of {prize}"));
+                }
+                if let Some(minutes) = repeat_minutes {
+                    line.push_str(&format!(", every {minutes} minutes"));
+                }
+                line
+            }
+            Self::ScheduleCancelled(id) => format!("Cancelled scheduled draw #{id}"),
+            Self::UnknownSchedule(id) => format!("No scheduled draw #{id}"),
+            Self::Excluded { count, reasons } => format!("Skipped {count} excluded: {reasons}"),
+            Self::ExcludedTicket => "listed tickets".into(),
+            Self::ExcludedByList => "on the exclude list".into(),
+            Self::ExcludedTag(tag) => format!("tagged {tag}"),
+            Self::ExcludedDomain(domain) => format!("with {domain} emails"),
+            Self::ExcludedEntry { name, reason } => {
+                format!("{name} is excluded from the raffle ({reason})")
+            }
+            Self::ExclusionAdded(value) => format!("Excluded {value} from the raffle"),
+            Self::ExcludedEntriesDropped(count) => {
+                format!("Took {count} matching entries out of the raffle.")
+            }
+            Self::ExclusionRemoved(value) => format!("{value} is no longer excluded"),
+            Self::NotExcluded(value) => format!("{value} is not on the exclude list"),
+            Self::NoExclusions => "The exclude list is empty.".into(),
+            Self::TicketExcluded(reference) => {
+                format!("Ticket {reference} is not part of the raffle.")
+            }
+            Self::Ineligible { name, reason } => {
+                format!("{name} can't enter the raffle: {reason}")
+            }
+            Self::BrokenRule(rule) => match rule {
+                Rule::ReleaseIn { .. } => "their release isn't part of the raffle".into(),
+                Rule::CheckedIn {
+                    after: None,
+                    before: None,
+                } => "not checked in".into(),
+                Rule::CheckedIn { .. } => "not checked in at the right time".into(),
+                Rule::Named => "no name on the ticket".into(),
+                Rule::TagPresent { tag } => format!("not tagged {tag}"),
+                Rule::TagAbsent { tag } => format!("tagged {tag}"),
+                Rule::NotPreviousWinner => "already won".into(),
+                Rule::QuestionAnswered { question } => format!("didn't answer {question}"),
+            },
+            Self::Odds { entries, eligible } => format!("{entries} entries, {eligible} can win."),
+            Self::NotInRaffle(name) => format!("{name} is not in the raffle"),
+            Self::CappedEntry { name, wins, cap } => {
+                format!("{name} has won {wins} times, the cap is {cap}. Draws skip them.")
+            }
+            Self::EntryOdds {
+                name,
+                entries,
+                percent,
+            } => format!("{name} has {entries} entries, a {percent:.1}% chance on the next draw."),
+            Self::AtWinCap(names) => format!("At the win cap, draws skip them: {names}"),
+            Self::NoHistory => "Nobody has been drawn yet.".into(),
+            Self::HistoryWin {
+                number,
+                name,
+                prize: Some(prize),
+                status,
+            } => format!("#{number} {name} won {prize} ({status})"),
+            Self::HistoryWin {
+                number,
+                name,
+                prize: None,
+                status,
+            } => format!("#{number} {name} ({status})"),
+            Self::Status(status) => match status {
+                WinnerStatus::Pending => "waiting to claim",
+                WinnerStatus::Claimed => "claimed",
+                WinnerStatus::NoShow => "no-show",
+                WinnerStatus::Expired => "claim expired",
+                WinnerStatus::Returned => "returned to the raffle",
+            }
+            .into(),
+            Self::HistorySkip { name, wins, cap } => {
+                format!("Skipped {name}, already won {wins} of {cap} allowed")
+            }
+            Self::StatsTitle => "Raffle stats".into(),
+            Self::StatsEntries => "Entries".into(),
+            Self::StatsReleases => "By release".into(),
+            Self::NoRelease => "Added by name".into(),
+            Self::StatsSources => "By source".into(),
+            Self::Source(source) => match source {
+                EntrySource::Tito => "Tito",
+                EntrySource::Manual => "Manual",
+                EntrySource::Import => "Import",
+                EntrySource::Attendee => "Self-entered",
+                EntrySource::Unknown => "Unknown",
+            }
+            .into(),
+            Self::StatsCheckedIn => "Checked in".into(),
+            Self::CheckedInCounts { checked_in, loaded } => {
+                format!("{checked_in} checked in, {loaded} loaded")
+            }
+            Self::StatsWinners => "Winners".into(),
+            Self::WinnerCounts { winners, claimed } => {
+                format!("{winners} drawn, {claimed} claimed")
+            }
+            Self::StatsCheckinTimes => "Check-ins over time".into(),
+            Self::Trigger(trigger) => match trigger {
+                Trigger::Timer => "on the timer",
+                Trigger::Clear => "before a clear",
+                Trigger::Remove => "before a removal",
+                Trigger::Restore => "before a restore",
+                Trigger::Import => "before an import",
+                Trigger::Exclude => "before an exclusion change",
+            }
+            .into(),
+            Self::SnapshotFailed => "Could not save or read snapshots, nothing was changed.".into(),
+            Self::NoSnapshots => "No snapshots yet.".into(),
+            Self::SnapshotLine {
+                id,
+                at,
+                trigger,
+                entries,
+                winners,
+            } => format!(
+                "#{id} {at}, {}: {entries} entries, {winners} winners",
+                Self::Trigger(*trigger).en()
+            ),
+            Self::UnknownSnapshot(id) => format!("No snapshot #{id}"),
+            Self::SnapshotUnchanged(id) => {
+                format!("The raffle still matches snapshot #{id}, nothing to restore.")
+            }
+            Self::RestorePreview { id, at, trigger } => format!(
+                "Restore snapshot #{id}, taken {at} {}?",
+                Self::Trigger(*trigger).en()
+            ),
+            Self::EntriesBack { count, names } => format!("{count} entries come back: {names}"),
+            Self::EntriesGone { count, names } => {
+                format!("{count} entries are taken out: {names}")
+            }
+            Self::WinnersChange { now, then } => {
+                format!("Winners: {now} now, {then} after restoring")
+            }
+            Self::ExcludedChange { now, then } => {
+                format!("Exclusions: {now} now, {then} after restoring")
+            }
+            Self::RestoreButton => "Restore".into(),
+            Self::CancelButton => "Cancel".into(),
+            Self::Restored { id, backup } => {
+                format!("Restored snapshot #{id}. The raffle from before is snapshot #{backup}.")
+            }
+            Self::RestoreCancelled => "Nothing was restored.".into(),
+            Self::InvalidEventId(id) => {
+                format!("\"{id}\" can't be an event id, use one word like cosmico-2025.")
+            }
+            Self::EventStarted { id, entries } => {
+                format!("Started event {id}, {entries} entries in its raffle.")
+            }
+            Self::SchedulesLeft { count, event } => format!(
+                "{count} scheduled draws stay with {event} and won't run until it's started again."
+            ),
+            Self::EventArchived(id) => format!(
+                "Event {id} is archived and read-only. Run `/event start` to begin the next one."
+            ),
+            Self::Archived { id, days } => format!(
+                "Archived event {id}. It's read-only and deleted in {days} days. \
+                 Run `/event start` to begin the next one."
+            ),
+            Self::ArchivedForGood(id) => format!(
+                "Archived event {id}. It's read-only and kept. \
+                 Run `/event start` to begin the next one."
+            ),
+            Self::Rehearsal { seed, winners } => format!(
+                "Rehearsal with seed {seed}, nobody was drawn. \
+                 The same seed on the same raffle picks:\n{winners}"
+            ),
+            Self::ReAdding(name) => format!("Re-adding **{name}**"),
+            Self::EntriesPage { count, page, pages } => {
+                format!("**{count} entries in the raffle** (page {page} of {pages})")
+            }
+            Self::PreviousButton => "Previous".into(),
+            Self::NextButton => "Next".into(),
+            Self::NoMatches(text) => format!("No entries match \"{text}\""),
+            Self::Matches { count, text } => format!("{count} entries match \"{text}\""),
+            Self::FirstMatches { shown, count, text } => {
+                format!("First {shown} of {count} entries matching \"{text}\"")
+            }
+            Self::AndMore(count) => format!("…and {count} more"),
+            Self::EnteredChoice { name, reference } => format!("{name} ({reference}) - entered"),
+            Self::Removed(name) => format!("Removed {name}"),
+            Self::ExportSummary { entries, winners } => {
+                format!("{entries} entries, {winners} winners")
+            }
+            Self::ExportFailed(error) => format!("Could not export the raffle: {error}"),
+            Self::Imported { added, duplicates } => {
+                format!("Imported {added} entries, skipped {duplicates} already in the raffle.")
+            }
+            Self::ImportRejected {
+                excluded,
+                ineligible,
+            } => format!("Rejected {excluded} excluded and {ineligible} ineligible."),
+            Self::UnknownReferences(references) => {
+                format!("Unknown ticket references: {references}")
+            }
+            Self::ReadFailed { file, error } => format!("Could not read {file}: {error}"),
+            Self::DownloadFailed { file, error } => format!("Could not download {file}: {error}"),
+            Self::ClaimButton => "Claim".into(),
+            Self::NoShowButton => "No-show".into(),
+            Self::ClaimStatus { status, name } => match status {
+                WinnerStatus::Pending => format!("{name} has yet to claim"),
+                WinnerStatus::Claimed => format!("✅ {name} claimed the prize"),
+                WinnerStatus::NoShow => format!("❌ {name} was a no-show, drawing again"),
+                WinnerStatus::Expired => format!("⌛ {name} didn't claim in time, drawing again"),
+                WinnerStatus::Returned => format!("↩️ {name} was returned to the raffle"),
+            },
+            Self::AlreadySettled => "This winner was already settled.".into(),
+            Self::NoReplacement => "No entries left to draw a replacement.".into(),
+            Self::TheWinner => "The winner".into(),
+            Self::WinnerTitle => "Winner".into(),
+            Self::NameField => "Name".into(),
+            Self::ReleaseField => "Release".into(),
+            Self::PrizeField => "Prize".into(),
+            Self::DrawField => "Draw #".into(),
+            Self::DmsClosed { mention, text } => {
+                format!("{mention} your DMs are closed, so here are the details:\n{text}")
+            }
+            Self::ReturnedToRaffle(name) => format!("Returned **{name}** to the raffle"),
+            Self::UnknownDraw(number) => format!("No record of draw #{number}"),
+            Self::NotAnnouncement => "That isn't a winner announcement.".into(),
+            Self::SettlePlaceholder => "Claim or no-show for…".into(),
+            Self::ReturnPlaceholder => "Winner to return…".into(),
+            Self::NothingToReturn => "Everyone on this page is back in the raffle.".into(),
+        }
+    }
+
+    fn es(&self) -> String {
+        match self {
+            Self::Restarting => "El bot se está reiniciando, inténtalo en un minuto.".into(),
+            Self::UnconfiguredGuild => "Este servidor no está configurado para sorteos.".into(),
+            Self::NotOrganizer => "Solo los organizadores del sorteo pueden hacer eso.".into(),
+            Self::Loaded { loaded, total } => {
+                format!("Se cargaron {loaded} participantes\n{total} participantes en total.")
+            }
+            Self::LoadFetched { tickets } => format!("Se obtuvieron {tickets} boletos…"),
+            Self::LoadFiltered { tickets, attendees } => format!(
+                "Se obtuvieron {tickets} boletos… {attendees} asistentes nuevos… cargando"
+            ),
+            Self::Drawing => "🎰 Sorteando…".into(),
+            Self::NoEntries => "No hay participantes en el sorteo.".into(),
+            Self::WinnersTitle {
+                count,
+                prize: Some(prize),
+            } => format!("🎉 {count} ganadores de {prize}"),
+            Self::WinnersTitle { count, prize: None } => format!("🎉 {count} ganadores"),
+            Self::Page { page, pages } => format!("Página {page} de {pages}"),
+            Self::FewerWinners { drawn, amount } => format!(
+                "Solo se sortearon {drawn} de los {amount} ganadores pedidos, \
+                 no quedan participantes que puedan ganar."
+            ),
+            Self::Cleared => "Lista borrada".into(),
+            Self::Added(name) => format!("Se agregó a {name}"),
+            Self::AlreadyInRaffle(name) => format!("{name} ya está en el sorteo"),
+            Self::NoNamedTicket(name) => {
+                format!("No hay un boleto a nombre de {name} en la lista de check-in")
+            }
+            Self::RaffleSize(size) => format!("{size} participantes en el sorteo"),
+            Self::TitoUnavailable => "No se pudo contactar a Tito, inténtalo en un minuto.".into(),
+            Self::UnknownTicket(reference) => {
+                format!("El boleto {reference} no está en la lista de check-in.")
+            }
+            Self::UnnamedTicket(reference) => format!(
+                "El boleto {reference} no tiene nombre, pregunta en el mostrador de registro."
+            ),
+            Self::IneligibleRelease(release) => {
+                format!("Los boletos {release} no participan en el sorteo.")
+            }
+            Self::NotCheckedIn(reference) => format!(
+                "El boleto {reference} aún no tiene check-in, pasa primero por el mostrador de registro."
+            ),
+            Self::TicketTaken(reference) => {
+                format!("El boleto {reference} ya está vinculado a otra cuenta de Discord.")
+            }
+            Self::AccountLinked(reference) => {
+                format!("Tu cuenta de Discord ya está vinculada al boleto {reference}.")
+            }
+            Self::Entered(name) => format!("Ya estás en el sorteo, {name}. ¡Buena suerte!"),
+            Self::AlreadyWon(name) => {
+                format!("{name} ya ganó, así que no puede volver a entrar al sorteo.")
+            }
+            Self::InvalidScheduleTime(at) => {
+                format!("No se entiende la hora {at}, usa HH:MM o AAAA-MM-DD HH:MM.")
+            }
+            Self::ScheduleInPast => "Esa hora ya pasó.".into(),
+            Self::Scheduled { id, at } => format!("Sorteo #{id} programado para {at}"),
+            Self::NoSchedules => "No hay sorteos programados.".into(),
+            Self::ScheduleLine {
+                id,
+                at,
+                amount,
+                prize,
+                repeat_minutes,
+            } => {
+                let mut line = format!("#{id} {at}: {amount} ganadores");
+                if let Some(prize) = prize {
+                    line.push_str(&format!(" de {prize}"));
+                }
+                if let Some(minutes) = repeat_minutes {
+                    line.push_str(&format!(", cada {minutes} minutos"));
+                }
+                line
+            }
+            Self::ScheduleCancelled(id) => format!("Se canceló el sorteo programado #{id}"),
+            Self::UnknownSchedule(id) => format!("No hay un sorteo programado #{id}"),
+            Self::Excluded { count, reasons } => {
+                format!("Se omitieron {count} excluidos: {reasons}")
+            }
+            Self::ExcludedTicket => "boletos en la lista".into(),
+            Self::ExcludedByList => "en la lista de exclusión".into(),
+            Self::ExcludedTag(tag) => format!("con la etiqueta {tag}"),
+            Self::ExcludedDomain(domain) => format!("con correo de {domain}"),
+            Self::ExcludedEntry { name, reason } => {
+                format!("{name} está excluido del sorteo ({reason})")
+            }
+            Self::ExclusionAdded(value) => format!("Se excluyó a {value} del sorteo"),
+            Self::ExcludedEntriesDropped(count) => {
+                format!("Se sacaron del sorteo {count} participantes que coinciden.")
+            }
+            Self::ExclusionRemoved(value) => format!("{value} ya no está excluido"),
+            Self::NotExcluded(value) => format!("{value} no está en la lista de exclusión"),
+            Self::NoExclusions => "La lista de exclusión está vacía.".into(),
+            Self::TicketExcluded(reference) => {
+                format!("El boleto {reference} no participa en el sorteo.")
+            }
+            Self::Ineligible { name, reason } => {
+                format!("{name} no puede participar en el sorteo: {reason}")
+            }
+            Self::BrokenRule(rule) => match rule {
+                Rule::ReleaseIn { .. } => "su tipo de boleto no participa".into(),
+                Rule::CheckedIn { after: None, before: None } => "no tiene check-in".into(),
+                Rule::CheckedIn { .. } => "no hizo check-in en el horario indicado".into(),
+                Rule::Named => "el boleto no tiene nombre".into(),
+                Rule::TagPresent { tag } => format!("no tiene la etiqueta {tag}"),
+                Rule::TagAbsent { tag } => format!("tiene la etiqueta {tag}"),
+                Rule::NotPreviousWinner => "ya ganó".into(),
+                Rule::QuestionAnswered { question } => format!("no respondió {question}"),
+            },
+            Self::Odds { entries, eligible } => {
+                format!("{entries} participantes, {eligible} pueden ganar.")
+            }
+            Self::NotInRaffle(name) => format!("{name} no está en el sorteo"),
+            Self::CappedEntry { name, wins, cap } => format!(
+                "{name} ya ganó {wins} veces, el máximo es {cap}. Los sorteos lo omiten."
+            ),
+            Self::EntryOdds {
+                name,
+                entries,
+                percent,
+            } => format!(
+                "{name} tiene {entries} participaciones, {percent:.1}% de probabilidad en el próximo sorteo."
+            ),
+            Self::AtWinCap(names) => {
+                format!("En el máximo de premios, los sorteos los omiten: {names}")
+            }
+            Self::NoHistory => "Todavía no se sorteó a nadie.".into(),
+            Self::HistoryWin {
+                number,
+                name,
+                prize: Some(prize),
+                status,
+            } => format!("#{number} {name} ganó {prize} ({status})"),
+            Self::HistoryWin {
+                number,
+                name,
+                prize: None,
+                status,
+            } => format!("#{number} {name} ({status})"),
+            Self::Status(status) => match status {
+                WinnerStatus::Pending => "esperando que lo reclame",
+                WinnerStatus::Claimed => "reclamado",
+                WinnerStatus::NoShow => "no se presentó",
+                WinnerStatus::Expired => "venció el plazo",
+                WinnerStatus::Returned => "devuelto al sorteo",
+            }
+            .into(),
+            Self::HistorySkip { name, wins, cap } => {
+                format!("Se omitió a {name}, ya ganó {wins} de {cap} permitidos")
+            }
+            Self::StatsTitle => "Estadísticas del sorteo".into(),
+            Self::StatsEntries => "Participantes".into(),
+            Self::StatsReleases => "Por tipo de boleto".into(),
+            Self::NoRelease => "Agregados por nombre".into(),
+            Self::StatsSources => "Por origen".into(),
+            Self::Source(source) => match source {
+                EntrySource::Tito => "Tito",
+                EntrySource::Manual => "A mano",
+                EntrySource::Import => "Importados",
+                EntrySource::Attendee => "Inscritos por sí mismos",
+                EntrySource::Unknown => "Desconocido",
+            }
+            .into(),
+            Self::StatsCheckedIn => "Check-in".into(),
+            Self::CheckedInCounts { checked_in, loaded } => {
+                format!("{checked_in} con check-in, {loaded} cargados")
+            }
+            Self::StatsWinners => "Ganadores".into(),
+            Self::WinnerCounts { winners, claimed } => {
+                format!("{winners} sorteados, {claimed} reclamados")
+            }
+            Self::StatsCheckinTimes => "Check-ins por hora".into(),
+            Self::Trigger(trigger) => match trigger {
+                Trigger::Timer => "automática",
+                Trigger::Clear => "antes de borrar",
+                Trigger::Remove => "antes de sacar a alguien",
+                Trigger::Restore => "antes de restaurar",
+                Trigger::Import => "antes de importar",
+                Trigger::Exclude => "antes de cambiar las exclusiones",
+            }
+            .into(),
+            Self::SnapshotFailed => {
+                "No se pudieron guardar o leer las copias, no se cambió nada.".into()
+            }
+            Self::NoSnapshots => "Todavía no hay copias.".into(),
+            Self::SnapshotLine {
+                id,
+                at,
+                trigger,
+                entries,
+                winners,
+            } => format!(
+                "#{id} {at}, {}: {entries} participantes, {winners} ganadores",
+                Self::Trigger(*trigger).es()
+            ),
+            Self::UnknownSnapshot(id) => format!("No hay una copia #{id}"),
+            Self::SnapshotUnchanged(id) => {
+                format!("El sorteo sigue igual que la copia #{id}, no hay nada que restaurar.")
+            }
+            Self::RestorePreview { id, at, trigger } => format!(
+                "¿Restaurar la copia #{id}, del {at} ({})?",
+                Self::Trigger(*trigger).es()
+            ),
+            Self::EntriesBack { count, names } => {
+                format!("Vuelven {count} participantes: {names}")
+            }
+            Self::EntriesGone { count, names } => format!("Salen {count} participantes: {names}"),
+            Self::WinnersChange { now, then } => {
+                format!("Ganadores: {now} ahora, {then} después de restaurar")
+            }
+            Self::ExcludedChange { now, then } => {
+                format!("Exclusiones: {now} ahora, {then} después de restaurar")
+            }
+            Self::RestoreButton => "Restaurar".into(),
+            Self::CancelButton => "Cancelar".into(),
+            Self::Restored { id, backup } => format!(
+                "Se restauró la copia #{id}. El sorteo de antes quedó en la copia #{backup}."
+            ),
+            Self::RestoreCancelled => "No se restauró nada.".into(),
+            Self::InvalidEventId(id) => format!(
+                "\"{id}\" no sirve como id de evento, usa una sola palabra como cosmico-2025."
+            ),
+            Self::EventStarted { id, entries } => {
+                format!("Comenzó el evento {id}, con {entries} participantes en su sorteo.")
+            }
+            Self::SchedulesLeft { count, event } => format!(
+                "{count} sorteos programados se quedan en {event} y no se harán \
+                 hasta que se comience de nuevo."
+            ),
+            Self::EventArchived(id) => format!(
+                "El evento {id} está archivado y es de solo lectura. \
+                 Usa `/event start` para comenzar el siguiente."
+            ),
+            Self::Archived { id, days } => format!(
+                "Se archivó el evento {id}. Es de solo lectura y se borra en {days} días. \
+                 Usa `/event start` para comenzar el siguiente."
+            ),
+            Self::ArchivedForGood(id) => format!(
+                "Se archivó el evento {id}. Es de solo lectura y se conserva. \
+                 Usa `/event start` para comenzar el siguiente."
+            ),
+            Self::Rehearsal { seed, winners } => format!(
+                "Ensayo con la semilla {seed}, no se sorteó a nadie. \
+                 La misma semilla con el mismo sorteo elige:\n{winners}"
+            ),
+            Self::ReAdding(name) => format!("Agregando de nuevo a **{name}**"),
+            Self::EntriesPage { count, page, pages } => {
+                format!("**{count} participantes en el sorteo** (página {page} de {pages})")
+            }
+            Self::PreviousButton => "Anterior".into(),
+            Self::NextButton => "Siguiente".into(),
+            Self::NoMatches(text) => format!("Ningún participante coincide con \"{text}\""),
+            Self::Matches { count, text } => {
+                format!("{count} participantes coinciden con \"{text}\"")
+            }
+            Self::FirstMatches { shown, count, text } => {
+                format!("Primeros {shown} de {count} participantes que coinciden con \"{text}\"")
+            }
+            Self::AndMore(count) => format!("…y {count} más"),
+            Self::EnteredChoice { name, reference } => {
+                format!("{name} ({reference}) - ya participa")
+            }
+            Self::Removed(name) => format!("Se sacó a {name}"),
+            Self::ExportSummary { entries, winners } => {
+                format!("{entries} participantes, {winners} ganadores")
+            }
+            Self::ExportFailed(error) => format!("No se pudo exportar el sorteo: {error}"),
+            Self::Imported { added, duplicates } => format!(
+                "Se importaron {added} participantes, se omitieron {duplicates} que ya estaban en el sorteo."
+            ),
+            Self::ImportRejected {
+                excluded,
+                ineligible,
+            } => format!("Se rechazaron {excluded} excluidos y {ineligible} que no pueden participar."),
+            Self::UnknownReferences(references) => {
+                format!("Referencias de boletos desconocidas: {references}")
+            }
+            Self::ReadFailed { file, error } => format!("No se pudo leer {file}: {error}"),
+            Self::DownloadFailed { file, error } => {
+                format!("No se pudo descargar {file}: {error}")
+            }
+            Self::ClaimButton => "Reclamar".into(),
+            Self::NoShowButton => "No se presentó".into(),
+            Self::ClaimStatus { status, name } => match status {
+                WinnerStatus::Pending => format!("{name} aún no reclama"),
+                WinnerStatus::Claimed => format!("✅ {name} reclamó el premio"),
+                WinnerStatus::NoShow => format!("❌ {name} no se presentó, sorteando de nuevo"),
+                WinnerStatus::Expired => {
+                    format!("⌛ {name} no reclamó a tiempo, sorteando de nuevo")
+                }
+                WinnerStatus::Returned => format!("↩️ {name} volvió al sorteo"),
+            },
+            Self::AlreadySettled => "Este ganador ya se resolvió.".into(),
+            Self::NoReplacement => "No quedan participantes para sortear un reemplazo.".into(),
+            Self::TheWinner => "El ganador".into(),
+            Self::WinnerTitle => "Ganador".into(),
+            Self::NameField => "Nombre".into(),
+            Self::ReleaseField => "Tipo de boleto".into(),
+            Self::PrizeField => "Premio".into(),
+            Self::DrawField => "Sorteo #".into(),
+            Self::DmsClosed { mention, text } => format!(
+                "{mention} tus mensajes directos están cerrados, así que aquí van los detalles:\n{text}"
+            ),
+            Self::ReturnedToRaffle(name) => format!("**{name}** volvió al sorteo"),
+            Self::UnknownDraw(number) => format!("No hay registro del sorteo #{number}"),
+            Self::NotAnnouncement => "Ese mensaje no anuncia a un ganador.".into(),
+            Self::SettlePlaceholder => "Reclamó o no se presentó…".into(),
+            Self::ReturnPlaceholder => "Ganador a devolver…".into(),
+            Self::NothingToReturn => "Todos en esta página ya volvieron al sorteo.".into(),
+        }
+    }
+}
+
+/// Spanish command descriptions, by their English text
+const ES_DESCRIPTIONS: &[(&str, &str)] = &[
+    ("Raffle Subcommand", "Comandos del sorteo"),
+    ("Pick a winner", "Elegir un ganador"),
+    ("Number of winners to pick", "Cantidad de ganadores"),
+    ("What the winners get", "Qué reciben los ganadores"),
+    (
+        "Spin through names once, then reveal all the winners",
+        "Mostrar nombres al azar una vez y luego revelar a todos los ganadores",
+    ),
+    ("Add an entry by hand", "Agregar un participante a mano"),
+    ("Entry's Full Name", "Nombre completo del participante"),
+    ("Clear raffle list", "Borrar la lista del sorteo"),
+    (
+        "Enter the raffle with your ticket",
+        "Participa en el sorteo con tu boleto",
+    ),
+    (
+        "Ticket reference, like ABCD-1",
+        "Referencia del boleto, como ABCD-1",
+    ),
+    ("Load tickets from tito", "Cargar boletos desde Tito"),
+    (
+        "Number of entries in the raffle",
+        "Cantidad de participantes en el sorteo",
+    ),
+    (
+        "Page through the entries",
+        "Ver los participantes por páginas",
+    ),
+    ("Find entries", "Buscar participantes"),
+    ("Part of the name", "Parte del nombre"),
+    (
+        "Take an entry out of the raffle",
+        "Sacar a un participante del sorteo",
+    ),
+    (
+        "Download entries and winners",
+        "Descargar participantes y ganadores",
+    ),
+    ("File format", "Formato del archivo"),
+    (
+        "Add entries from a CSV of names or ticket references",
+        "Agregar participantes desde un CSV de nombres o referencias de boletos",
+    ),
+    ("CSV file", "Archivo CSV"),
+    ("Return to raffle", "Devolver al sorteo"),
+    ("Draw on a schedule", "Sortear a una hora programada"),
+    ("Schedule a draw", "Programar un sorteo"),
+    (
+        "HH:MM or YYYY-MM-DD HH:MM, in the event's time zone",
+        "HH:MM o AAAA-MM-DD HH:MM, en la zona horaria del evento",
+    ),
+    (
+        "Minutes between draws, runs once when unset",
+        "Minutos entre sorteos, se sortea una vez si no se indica",
+    ),
+    ("List scheduled draws", "Ver los sorteos programados"),
+    ("Cancel a scheduled draw", "Cancelar un sorteo programado"),
+    ("Scheduled draw number", "Número del sorteo programado"),
+    (
+        "Keep tickets out of the raffle",
+        "Dejar boletos fuera del sorteo",
+    ),
+    (
+        "Exclude a ticket, name or email",
+        "Excluir un boleto, nombre o correo",
+    ),
+    (
+        "Ticket reference, full name or email",
+        "Referencia del boleto, nombre completo o correo",
+    ),
+    (
+        "Take a ticket, name or email off the exclude list",
+        "Quitar un boleto, nombre o correo de la lista de exclusión",
+    ),
+    ("Show the exclude list", "Ver la lista de exclusión"),
+    (
+        "Chance of winning the next draw",
+        "Probabilidad de ganar el próximo sorteo",
+    ),
+    (
+        "Only this entry's chance",
+        "Solo la probabilidad de este participante",
+    ),
+    (
+        "Winners and skipped entries so far",
+        "Ganadores y participantes omitidos hasta ahora",
+    ),
+    (
+        "Numbers about entries, check-ins and winners",
+        "Cifras de participantes, check-ins y ganadores",
+    ),
+    ("List snapshots of the raffle", "Ver las copias del sorteo"),
+    (
+        "Roll the raffle back to a snapshot",
+        "Volver el sorteo a una copia",
+    ),
+    ("Snapshot number", "Número de la copia"),
+    ("Event Subcommand", "Comandos del evento"),
+    (
+        "Start a new event with an empty raffle",
+        "Comenzar un evento nuevo con el sorteo vacío",
+    ),
+    (
+        "Event id, like cosmico-2025",
+        "Id del evento, como cosmico-2025",
+    ),
+    (
+        "Make the current event read-only until it expires",
+        "Dejar el evento actual en solo lectura hasta que venza",
+    ),
+    (
+        "Draw from a copy of the raffle, nobody wins",
+        "Sortear sobre una copia del sorteo, nadie gana",
+    ),
+    ("Same seed, same winners", "Misma semilla, mismos ganadores"),
+];
+
+/// Translations of an English command description or name, as pairs of
+/// Discord locale and text
+pub fn localizations(english: &str) -> Vec<(&'static str, &'static str)> {
+    ES_DESCRIPTIONS
+        .iter()
+        .filter(|(en, _)| *en == english)
+        .flat_map(|(_, es)| {
+            Locale::Es
+                .discord_locales()
+                .iter()
+                .map(move |locale| (*locale, *es))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_locale() {
+        assert_eq!(Locale::resolve(None, "es-419"), Locale::Es);
+        assert_eq!(Locale::resolve(None, "fr"), Locale::En);
+        assert_eq!(Locale::resolve(Some(Locale::En), "es-ES"), Locale::En);
+    }
+
+    #[test]
+    fn localizes_descriptions() {
+        assert_eq!(
+            localizations("Pick a winner"),
+            vec![
+                ("es-ES", "Elegir un ganador"),
+                ("es-419", "Elegir un ganador")
+            ]
+        );
+        assert!(localizations("Not a description").is_empty());
+        assert_eq!(
+            Msg::RaffleSize(3).text(Locale::Es),
+            "3 participantes en el sorteo"
+        );
+    }
+}
@@ -1,8 +1,30 @@
+pub mod config;
 pub mod discord;
+pub mod eligibility;
+pub mod i18n;
+pub mod metrics;
+pub mod shutdown;
+pub mod status;
 pub mod tito;
 
+use bb8::Pool;
+use bb8_redis::RedisConnectionManager;
 use reqwest::{header, ClientBuilder};
 
+/// Setup and return an async redis pool
+pub async fn redis_pool(
+    redis_str: &str,
+    max_size: u32,
+) -> Result<Pool<RedisConnectionManager>, redis::RedisError> {
+    // Heroku Redis uses self signed certs, so need to set OPENSSL_VERIFY_NONE
+    // https://devcenter.heroku.com/articles/heroku-redis#security-and-compliance
+    let mut url = url::Url::parse(redis_str).unwrap();
+    url.set_fragment(Some("insecure"));
+
+    let manager = RedisConnectionManager::new(url)?;
+    Pool::builder().max_size(max_size).build(manager).await
+}
+
 pub async fn tito_test() {
     let mut headers = header::HeaderMap::new();
     let tito_api_token = std::env::var("TITO_API_TOKEN").unwrap();
@@ -1,46 +1,62 @@
-use bb8::Pool;
-use bb8_redis::RedisConnectionManager;
 use casino_cosmico::{
-    discord::{commands, type_map_keys},
-    tito,
+    config::{CommandScope, Config, GuildConfig},
+    discord::{
+        commands::{
+            self, announce, claims, eligibility, enter, entries, events, exclusions, export,
+            history, readd, rehearse,
+            rng::{self, DrawRng},
+            schedule, snapshots, stats, RedisKeys,
+        },
+        type_map_keys,
+    },
+    i18n::{self, Locale, Msg},
+    metrics, redis_pool, shutdown, status, tito,
 };
-use lazy_static::lazy_static;
-use rand::SeedableRng;
-use regex::Regex;
 use serenity::{
     async_trait,
-    builder::{CreateCommand, CreateCommandOption, CreateMessage},
+    builder::{
+        CreateCommand, CreateCommandOption, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateMessage,
+    },
     client::{Context, EventHandler},
     http::Http,
     model::{
-        application::{CommandDataOptionValue, CommandInteraction, CommandOptionType, Interaction},
+        application::{
+            Command, CommandDataOption, CommandDataOptionValue, CommandInteraction,
+            CommandOptionType, Interaction,
+        },
         channel::{Reaction, ReactionType},
         gateway::{GatewayIntents, Ready},
-        id::{ApplicationId, ChannelId, GuildId},
+        guild::Member,
+        id::ApplicationId,
     },
     prelude::RwLock,
     Error as SerenityError,
 };
-use std::{env, sync::Arc};
+use std::{env, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::signal::unix::{signal, SignalKind};
 use tracing::{error, info, instrument};
 
-const EARLY_BIRD_TICKET_SLUG: &str = "Con of Heroes 2024 Early Bird Ticket";
-const GENERAL_TICKET_SLUG: &str = "Con of heroes 2024 General Ticket";
-const NO_SWAG_TICKET_SLUG: &str = "No-SWAG ticket";
-const TICKET_SPOOFER_SLUG: &str = "Ticket spoofer";
-const LOADED_REDIS_KEY: &str = "loaded";
-const RAFFLE_REDIS_KEY: &str = "raffle";
-
-/// Setup and return an async redis pool
-async fn redis_pool(redis_str: &str) -> Result<Pool<RedisConnectionManager>, redis::RedisError> {
-    // Heroku Redis uses self signed certs, so need to set OPENSSL_VERIFY_NONE
-    // https://devcenter.heroku.com/articles/heroku-redis#security-and-compliance
-    let mut url = url::Url::parse(redis_str).unwrap();
-    url.set_fragment(Some("insecure"));
-
-    let manager = RedisConnectionManager::new(url)?;
-    Pool::builder().build(manager).await
-}
+const DEFAULT_STATUS_PORT: u16 = 8080;
+const RAFFLE_DESCRIPTION: &str = "Raffle Subcommand";
+const EVENT_DESCRIPTION: &str = "Event Subcommand";
+/// Sub-commands anyone in the guild may run
+const ATTENDEE_SUB_COMMANDS: &[&str] = &["enter"];
+/// Sub-commands that still work once the event is archived
+const READ_ONLY_SUB_COMMANDS: &[&str] = &[
+    "size",
+    "list",
+    "search",
+    "export",
+    "odds",
+    "history",
+    "stats",
+    "snapshots",
+    "rehearse",
+];
+const REDIS_POOL_MAX_SIZE: u32 = 10;
+/// Heroku sends SIGKILL 30 seconds after SIGTERM
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(20);
 
 #[derive(thiserror::Error, Debug)]
 enum SlashCommandError {
@@ -50,87 +66,573 @@ enum SlashCommandError {
     NoSubCommand,
     #[error("Unknown Sub-Command")]
     UnknownSubCommand,
+    #[error("Guild is not configured")]
+    UnconfiguredGuild,
+    #[error("Member is not an organizer")]
+    NotOrganizer,
+    #[error("Event {0} is archived")]
+    ArchivedEvent(String),
     #[error("Serenity Error: {0}")]
     Serenity(#[from] SerenityError),
 }
 
-struct SlashHandler;
+impl SlashCommandError {
+    /// Message shown to the user when the command was refused
+    fn reply(&self) -> Option<Msg<'_>> {
+        match self {
+            Self::UnconfiguredGuild => Some(Msg::UnconfiguredGuild),
+            Self::NotOrganizer => Some(Msg::NotOrganizer),
+            Self::ArchivedEvent(id) => Some(Msg::EventArchived(id)),
+            _ => None,
+        }
+    }
+}
 
-#[async_trait]
-impl EventHandler for SlashHandler {
-    async fn ready(&self, ctx: Context, ready: Ready) {
-        info!("{} is connected!", ready.user.name);
+/// Every application command the bot registers
+fn commands() -> Vec<CreateCommand> {
+    vec![raffle_command(), event_command(), readd::command()]
+}
 
-        let guild_id = type_map_keys::GuildId::get(&ctx.data).await;
-        guild_id
-            .create_command(
-                &ctx.http,
-                CreateCommand::new("raffle")
-                    .description("Raffle Subcommand")
-                    .add_option(
-                        CreateCommandOption::new(
-                            CommandOptionType::SubCommand,
-                            "pick",
-                            "Pick a winner",
+/// The `/event` command, which picks the event raffles run in
+fn event_command() -> CreateCommand {
+    let mut command = CreateCommand::new(events::COMMAND_NAME).description(EVENT_DESCRIPTION);
+    for (locale, description) in i18n::localizations(EVENT_DESCRIPTION) {
+        command = command.description_localized(locale, description);
+    }
+
+    command
+        .add_option(
+            localized_option(
+                CommandOptionType::SubCommand,
+                "start",
+                "Start a new event with an empty raffle",
+            )
+            .add_sub_option(
+                localized_option(
+                    CommandOptionType::String,
+                    "id",
+                    "Event id, like cosmico-2025",
+                )
+                .required(true),
+            ),
+        )
+        .add_option(localized_option(
+            CommandOptionType::SubCommand,
+            "archive",
+            "Make the current event read-only until it expires",
+        ))
+}
+
+/// The `/raffle` command and its sub-commands
+fn raffle_command() -> CreateCommand {
+    let mut command = CreateCommand::new("raffle").description(RAFFLE_DESCRIPTION);
+    for (locale, description) in i18n::localizations(RAFFLE_DESCRIPTION) {
+        command = command.description_localized(locale, description);
+    }
+
+    command
+        .add_option(
+            localized_option(CommandOptionType::SubCommand, "pick", "Pick a winner")
+                .add_sub_option(
+                    localized_option(
+                        CommandOptionType::Integer,
+                        "amount",
+                        "Number of winners to pick",
+                    )
+                    .min_int_value(1),
+                )
+                .add_sub_option(localized_option(
+                    CommandOptionType::String,
+                    "prize",
+                    "What the winners get",
+                ))
+                .add_sub_option(localized_option(
+                    CommandOptionType::Boolean,
+                    "dramatic",
+                    "Spin through names once, then reveal all the winners",
+                )),
+        )
+        .add_option(
+            localized_option(CommandOptionType::SubCommand, "add", "Add an entry by hand")
+                .add_sub_option(
+                    localized_option(CommandOptionType::String, "name", "Entry's Full Name")
+                        .required(true),
+                ),
+        )
+        .add_option(localized_option(
+            CommandOptionType::SubCommand,
+            "clear",
+            "Clear raffle list",
+        ))
+        .add_option(
+            localized_option(
+                CommandOptionType::SubCommand,
+                "enter",
+                "Enter the raffle with your ticket",
+            )
+            .add_sub_option(
+                localized_option(
+                    CommandOptionType::String,
+                    "reference",
+                    "Ticket reference, like ABCD-1",
+                )
+                .required(true),
+            ),
+        )
+        .add_option(localized_option(
+            CommandOptionType::SubCommand,
+            "load",
+            "Load tickets from tito",
+        ))
+        .add_option(localized_option(
+            CommandOptionType::SubCommand,
+            "size",
+            "Number of entries in the raffle",
+        ))
+        .add_option(localized_option(
+            CommandOptionType::SubCommand,
+            "list",
+            "Page through the entries",
+        ))
+        .add_option(
+            localized_option(
+                CommandOptionType::SubCommand,
+                "odds",
+                "Chance of winning the next draw",
+            )
+            .add_sub_option(localized_option(
+                CommandOptionType::String,
+                "name",
+                "Only this entry's chance",
+            )),
+        )
+        .add_option(localized_option(
+            CommandOptionType::SubCommand,
+            "history",
+            "Winners and skipped entries so far",
+        ))
+        .add_option(localized_option(
+            CommandOptionType::SubCommand,
+            "stats",
+            "Numbers about entries, check-ins and winners",
+        ))
+        .add_option(
+            localized_option(
+                CommandOptionType::SubCommand,
+                "rehearse",
+                "Draw from a copy of the raffle, nobody wins",
+            )
+            .add_sub_option(
+                localized_option(
+                    CommandOptionType::Integer,
+                    "seed",
+                    "Same seed, same winners",
+                )
+                .min_int_value(0)
+                .max_int_value(rng::MAX_SEED),
+            )
+            .add_sub_option(
+                localized_option(
+                    CommandOptionType::Integer,
+                    "amount",
+                    "Number of winners to pick",
+                )
+                .min_int_value(1),
+            ),
+        )
+        .add_option(localized_option(
+            CommandOptionType::SubCommand,
+            "snapshots",
+            "List snapshots of the raffle",
+        ))
+        .add_option(
+            localized_option(
+                CommandOptionType::SubCommand,
+                "restore",
+                "Roll the raffle back to a snapshot",
+            )
+            .add_sub_option(
+                localized_option(CommandOptionType::Integer, "id", "Snapshot number")
+                    .min_int_value(1)
+                    .required(true),
+            ),
+        )
+        .add_option(
+            localized_option(CommandOptionType::SubCommand, "search", "Find entries")
+                .add_sub_option(
+                    localized_option(CommandOptionType::String, "text", "Part of the name")
+                        .required(true),
+                ),
+        )
+        .add_option(
+            localized_option(
+                CommandOptionType::SubCommand,
+                "remove",
+                "Take an entry out of the raffle",
+            )
+            .add_sub_option(
+                localized_option(CommandOptionType::String, "name", "Entry's Full Name")
+                    .required(true),
+            ),
+        )
+        .add_option(
+            localized_option(
+                CommandOptionType::SubCommand,
+                "export",
+                "Download entries and winners",
+            )
+            .add_sub_option(
+                localized_option(CommandOptionType::String, "format", "File format")
+                    .add_string_choice("CSV", "csv")
+                    .add_string_choice("JSON", "json")
+                    .required(true),
+            ),
+        )
+        .add_option(
+            localized_option(
+                CommandOptionType::SubCommand,
+                "import",
+                "Add entries from a CSV of names or ticket references",
+            )
+            .add_sub_option(
+                localized_option(CommandOptionType::Attachment, "file", "CSV file").required(true),
+            ),
+        )
+        .add_option(
+            localized_option(
+                CommandOptionType::SubCommandGroup,
+                "exclude",
+                "Keep tickets out of the raffle",
+            )
+            .add_sub_option(
+                localized_option(
+                    CommandOptionType::SubCommand,
+                    "add",
+                    "Exclude a ticket, name or email",
+                )
+                .add_sub_option(
+                    localized_option(
+                        CommandOptionType::String,
+                        "value",
+                        "Ticket reference, full name or email",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_sub_option(
+                localized_option(
+                    CommandOptionType::SubCommand,
+                    "remove",
+                    "Take a ticket, name or email off the exclude list",
+                )
+                .add_sub_option(
+                    localized_option(
+                        CommandOptionType::String,
+                        "value",
+                        "Ticket reference, full name or email",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_sub_option(localized_option(
+                CommandOptionType::SubCommand,
+                "list",
+                "Show the exclude list",
+            )),
+        )
+        .add_option(
+            localized_option(
+                CommandOptionType::SubCommandGroup,
+                "schedule",
+                "Draw on a schedule",
+            )
+            .add_sub_option(
+                localized_option(CommandOptionType::SubCommand, "pick", "Schedule a draw")
+                    .add_sub_option(
+                        localized_option(
+                            CommandOptionType::String,
+                            "at",
+                            "HH:MM or YYYY-MM-DD HH:MM, in the event's time zone",
                         )
-                        .add_sub_option(
-                            CreateCommandOption::new(
-                                CommandOptionType::Integer,
-                                "amount",
-                                "Number of winners to pick",
-                            )
-                            .min_int_value(1),
-                        ),
+                        .required(true),
                     )
-                    .add_option(
-                        CreateCommandOption::new(
-                            CommandOptionType::SubCommand,
-                            "add",
-                            "Add an entry by hand",
+                    .add_sub_option(
+                        localized_option(
+                            CommandOptionType::Integer,
+                            "amount",
+                            "Number of winners to pick",
                         )
-                        .add_sub_option(
-                            CreateCommandOption::new(
-                                CommandOptionType::String,
-                                "name",
-                                "Entry's Full Name",
-                            )
-                            .required(true),
-                        ),
+                        .min_int_value(1),
                     )
-                    .add_option(CreateCommandOption::new(
-                        CommandOptionType::SubCommand,
-                        "clear",
-                        "Clear raffle list",
+                    .add_sub_option(localized_option(
+                        CommandOptionType::String,
+                        "prize",
+                        "What the winners get",
                     ))
-                    .add_option(CreateCommandOption::new(
-                        CommandOptionType::SubCommand,
-                        "load",
-                        "Load tickets from tito",
-                    ))
-                    .add_option(CreateCommandOption::new(
-                        CommandOptionType::SubCommand,
-                        "size",
-                        "Number of entries in the raffle",
+                    .add_sub_option(
+                        localized_option(
+                            CommandOptionType::Integer,
+                            "repeat",
+                            "Minutes between draws, runs once when unset",
+                        )
+                        .min_int_value(1),
+                    )
+                    .add_sub_option(localized_option(
+                        CommandOptionType::Boolean,
+                        "dramatic",
+                        "Spin through names once, then reveal all the winners",
                     )),
             )
-            .await
-            .unwrap();
+            .add_sub_option(localized_option(
+                CommandOptionType::SubCommand,
+                "list",
+                "List scheduled draws",
+            ))
+            .add_sub_option(
+                localized_option(
+                    CommandOptionType::SubCommand,
+                    "cancel",
+                    "Cancel a scheduled draw",
+                )
+                .add_sub_option(
+                    localized_option(CommandOptionType::Integer, "id", "Scheduled draw number")
+                        .required(true),
+                ),
+            ),
+        )
+}
+
+/// An option whose description is translated wherever the catalog has it
+fn localized_option(kind: CommandOptionType, name: &str, description: &str) -> CreateCommandOption {
+    let mut option = CreateCommandOption::new(kind, name, description);
+    for (locale, description) in i18n::localizations(description) {
+        option = option.description_localized(locale, description);
+    }
+
+    option
+}
+
+struct SlashHandler;
+
+#[async_trait]
+impl EventHandler for SlashHandler {
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        info!("{} is connected!", ready.user.name);
+
+        claims::watch(ctx.clone());
+        schedule::watch(ctx.clone());
+        snapshots::watch(ctx.clone());
+
+        let config = type_map_keys::Config::get(&ctx.data).await;
+        match config.command_scope {
+            CommandScope::Global => {
+                if let Err(err) = Command::set_global_commands(&ctx.http, commands()).await {
+                    error!("Cannot register global commands: {}", err);
+                }
+            }
+            CommandScope::Guild => {
+                for guild_id in config.guilds.keys() {
+                    if let Err(err) = guild_id.set_commands(&ctx.http, commands()).await {
+                        error!("Cannot register commands for guild {}: {}", guild_id, err);
+                    }
+                }
+            }
+        }
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if let Interaction::Autocomplete(autocomplete) = &interaction {
+            let config = type_map_keys::Config::get(&ctx.data).await;
+            let guild = autocomplete
+                .guild_id
+                .and_then(|guild_id| config.guild(guild_id));
+            // attendee names are only for organizers
+            if let Some(guild) =
+                guild.filter(|guild| is_organizer(autocomplete.member.as_deref(), guild))
+            {
+                let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+                let event = events::current(&redis_pool, guild).await;
+                if let Err(err) = commands::autocomplete::add_name(
+                    &ctx,
+                    autocomplete,
+                    &event.keys(guild),
+                    &guild.checkin_list_slug,
+                    Locale::resolve(guild.locale, &autocomplete.locale),
+                )
+                .await
+                {
+                    error!("Cannot autocomplete: {}", err);
+                }
+            }
+            return;
+        }
+
+        if let Interaction::Component(component) = &interaction {
+            let config = type_map_keys::Config::get(&ctx.data).await;
+            let guild = component
+                .guild_id
+                .and_then(|guild_id| config.guild(guild_id))
+                .filter(|guild| is_organizer(component.member.as_ref(), guild));
+            let Some(guild) = guild else {
+                return;
+            };
+            let locale = Locale::resolve(guild.locale, &component.locale);
+            // buttons redraw and restore, which mustn't be cut off halfway
+            let shutdown = type_map_keys::Shutdown::get(&ctx.data).await;
+            let Some(_in_flight) = shutdown.track() else {
+                if let Err(err) = component
+                    .create_response(
+                        &ctx.http,
+                        CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::new()
+                                .content(Msg::Restarting.text(locale))
+                                .ephemeral(true),
+                        ),
+                    )
+                    .await
+                {
+                    error!("Cannot reply to button: {}", err);
+                }
+                return;
+            };
+            let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+            let event = events::current(&redis_pool, guild).await;
+            let keys = event.keys(guild);
+            let custom_id = component.data.custom_id.as_str();
+
+            if let Some(page) = custom_id.strip_prefix(entries::LIST_BUTTON_PREFIX) {
+                if let Ok(page) = page.parse() {
+                    if let Err(err) =
+                        entries::list_button(&ctx, component, &keys, page, locale).await
+                    {
+                        error!("Cannot turn the page: {}", err);
+                    }
+                }
+            } else if let Some(ids) = custom_id.strip_prefix(announce::SUMMARY_BUTTON_PREFIX) {
+                if let Err(err) = announce::summary_button(&ctx, component, guild, &keys, ids).await
+                {
+                    error!("Cannot turn the page: {}", err);
+                }
+            } else if event.archived {
+                if let Err(err) = component
+                    .create_response(
+                        &ctx.http,
+                        CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::new()
+                                .content(Msg::EventArchived(&event.id).text(locale))
+                                .ephemeral(true),
+                        ),
+                    )
+                    .await
+                {
+                    error!("Cannot reply to button: {}", err);
+                }
+            } else if custom_id.starts_with(snapshots::RESTORE_BUTTON_PREFIX) {
+                if let Err(err) = snapshots::restore_button(&ctx, component, guild, &keys).await {
+                    error!("Cannot restore snapshot: {}", err);
+                }
+            } else if custom_id.starts_with(claims::SETTLE_MENU_PREFIX) {
+                if let Err(err) = claims::menu(&ctx, component, guild, &keys).await {
+                    error!("Cannot show winner: {}", err);
+                }
+            } else if let Some(message_id) = custom_id.strip_prefix(readd::RETURN_MENU_PREFIX) {
+                if let Err(err) = readd::menu(&ctx, component, guild, &keys, message_id).await {
+                    error!("Cannot return winner: {}", err);
+                }
+            } else if custom_id.starts_with(claims::CLAIM_BUTTON_PREFIX)
+                || custom_id.starts_with(claims::NO_SHOW_BUTTON_PREFIX)
+            {
+                if let Err(err) = claims::button(&ctx, component, guild, &keys).await {
+                    error!("Cannot settle claim: {}", err);
+                }
+            }
+            return;
+        }
+
         if let Interaction::Command(command) = interaction {
-            if command.data.name.as_str() != "raffle" {
+            if !["raffle", events::COMMAND_NAME, readd::COMMAND_NAME]
+                .contains(&command.data.name.as_str())
+            {
                 return;
             }
 
-            if let Err(err) = match_subcommand(&ctx, &command).await {
+            let config = type_map_keys::Config::get(&ctx.data).await;
+            let guild = command.guild_id.and_then(|guild_id| config.guild(guild_id));
+            let locale = Locale::resolve(guild.and_then(|guild| guild.locale), &command.locale);
+            let shutdown = type_map_keys::Shutdown::get(&ctx.data).await;
+            let Some(_in_flight) = shutdown.track() else {
+                if let Err(err) = command
+                    .create_response(
+                        &ctx.http,
+                        CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::new()
+                                .content(Msg::Restarting.text(locale))
+                                .ephemeral(true),
+                        ),
+                    )
+                    .await
+                {
+                    error!("Cannot reply to slash command: {}", err);
+                }
+                return;
+            };
+
+            let result = match guild {
+                Some(guild) if command.data.name == readd::COMMAND_NAME => {
+                    return_to_raffle(&ctx, &command, guild).await
+                }
+                Some(guild) if command.data.name == events::COMMAND_NAME => {
+                    match_event(&ctx, &command, guild).await
+                }
+                Some(guild) => match_subcommand(&ctx, &command, guild).await,
+                None => Err(SlashCommandError::UnconfiguredGuild),
+            };
+            let subcommand = command
+                .data
+                .options
+                .first()
+                .map_or(command.data.name.as_str(), |option| option.name.as_str());
+            metrics::COMMANDS
+                .with_label_values(&[subcommand, if result.is_ok() { "ok" } else { "error" }])
+                .inc();
+
+            if let Err(err) = result {
                 error!("Cannot respond to slash comamnd: {}", err);
+                if let Some(reply) = err.reply() {
+                    if let Err(err) = command
+                        .create_response(
+                            &ctx.http,
+                            CreateInteractionResponse::Message(
+                                CreateInteractionResponseMessage::new()
+                                    .content(reply.text(locale))
+                                    .ephemeral(true),
+                            ),
+                        )
+                        .await
+                    {
+                        error!("Cannot reply to slash command: {}", err);
+                    }
+                }
             }
         }
     }
 
     async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
-        let channel_id = type_map_keys::ChannelId::get(&ctx.data).await;
+        let config = type_map_keys::Config::get(&ctx.data).await;
+        let Some(guild) = reaction
+            .guild_id
+            .and_then(|guild_id| config.guild(guild_id))
+        else {
+            return;
+        };
+        if !guild.legacy_reaction_readd {
+            return;
+        }
+        let shutdown = type_map_keys::Shutdown::get(&ctx.data).await;
+        let Some(_in_flight) = shutdown.track() else {
+            return;
+        };
+        let channel_id = guild.channel_id;
         let user_id = type_map_keys::UserId::get(&ctx.data).await;
 
         if reaction.channel_id == channel_id {
@@ -138,21 +640,22 @@ impl EventHandler for SlashHandler {
             if message.author.id == user_id {
                 if let ReactionType::Unicode(ref code) = reaction.emoji {
                     if code == "👍" {
-                        lazy_static! {
-                            static ref RE: Regex =
-                                Regex::new(r"[*]{2}(?P<name>[^*]+)[*]{2}").unwrap();
-                        }
-
-                        let contents = message.content;
-                        if let Some(caps) = RE.captures(&contents) {
-                            let name = &caps["name"];
-                            commands::add_name(&ctx, LOADED_REDIS_KEY, RAFFLE_REDIS_KEY, name)
+                        let name = message.embeds.iter().find_map(announce::winner_name);
+                        if let Some(name) = name {
+                            let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+                            let event = events::current(&redis_pool, guild).await;
+                            if event.archived {
+                                return;
+                            }
+                            commands::add_name(&redis_pool, &event.keys(guild), name)
                                 .await
                                 .unwrap();
                             channel_id
                                 .send_message(
                                     &ctx.http,
-                                    CreateMessage::new().content(format!("Re-adding **{name}**")),
+                                    CreateMessage::new().content(
+                                        Msg::ReAdding(name).text(guild.locale.unwrap_or_default()),
+                                    ),
                                 )
                                 .await
                                 .unwrap();
@@ -164,31 +667,123 @@ impl EventHandler for SlashHandler {
     }
 }
 
+fn is_organizer(member: Option<&Member>, guild: &GuildConfig) -> bool {
+    member.is_some_and(|member| {
+        member
+            .permissions
+            .is_some_and(|permissions| permissions.administrator())
+            || guild.is_organizer(&member.roles)
+    })
+}
+
+#[allow(clippy::result_large_err)]
+async fn return_to_raffle(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+) -> Result<(), SlashCommandError> {
+    if !is_organizer(command.member.as_deref(), guild) {
+        return Err(SlashCommandError::NotOrganizer);
+    }
+
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let event = events::current(&redis_pool, guild).await;
+    if event.archived {
+        return Err(SlashCommandError::ArchivedEvent(event.id));
+    }
+
+    let locale = Locale::resolve(guild.locale, &command.locale);
+    readd::return_to_raffle(ctx, command, &event.keys(guild), locale)
+        .await
+        .map_err(|err| err.into())
+}
+
+/// Whether a sub-command only reads, so it may run on an archived event
+fn is_read_only(sub_cmd: &CommandDataOption) -> bool {
+    match &sub_cmd.value {
+        CommandDataOptionValue::SubCommandGroup(group) => {
+            group.first().is_some_and(|sub_cmd| sub_cmd.name == "list")
+        }
+        _ => READ_ONLY_SUB_COMMANDS.contains(&sub_cmd.name.as_str()),
+    }
+}
+
+/// Maps `/event` Sub-Commands to function calls
+#[allow(clippy::result_large_err)]
+async fn match_event(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+) -> Result<(), SlashCommandError> {
+    if !is_organizer(command.member.as_deref(), guild) {
+        return Err(SlashCommandError::NotOrganizer);
+    }
+    let locale = Locale::resolve(guild.locale, &command.locale);
+    let sub_cmd = command
+        .data
+        .options
+        .first()
+        .ok_or(SlashCommandError::NoSubCommand)?;
+    let CommandDataOptionValue::SubCommand(options) = &sub_cmd.value else {
+        return Err(SlashCommandError::UnknownSubCommand);
+    };
+    match sub_cmd.name.as_str() {
+        "start" => {
+            let Some(CommandDataOptionValue::String(id)) = option(options, "id") else {
+                return Err(SlashCommandError::MissingOption(
+                    "start".into(),
+                    "id".into(),
+                ));
+            };
+            events::start(ctx, command, guild, id, locale)
+                .await
+                .map_err(|err| err.into())
+        }
+        "archive" => events::archive(ctx, command, guild, locale)
+            .await
+            .map_err(|err| err.into()),
+        _ => Err(SlashCommandError::UnknownSubCommand),
+    }
+}
+
 /// Maps Slash Sub-Commands to function calls
+#[allow(clippy::result_large_err)]
 async fn match_subcommand(
     ctx: &Context,
     command: &CommandInteraction,
+    guild: &GuildConfig,
 ) -> Result<(), SlashCommandError> {
+    let locale = Locale::resolve(guild.locale, &command.locale);
     let sub_cmd = command
         .data
         .options
-        .get(0)
+        .first()
         .ok_or(SlashCommandError::NoSubCommand)?;
+    if !ATTENDEE_SUB_COMMANDS.contains(&sub_cmd.name.as_str())
+        && !is_organizer(command.member.as_deref(), guild)
+    {
+        return Err(SlashCommandError::NotOrganizer);
+    }
+    let redis_pool = type_map_keys::RedisPool::get(&ctx.data).await;
+    let event = events::current(&redis_pool, guild).await;
+    if event.archived && !is_read_only(sub_cmd) {
+        return Err(SlashCommandError::ArchivedEvent(event.id));
+    }
+    let keys = event.keys(guild);
+    if let CommandDataOptionValue::SubCommandGroup(group) = &sub_cmd.value {
+        return match sub_cmd.name.as_str() {
+            "exclude" => match_exclude(ctx, command, guild, &keys, group, locale).await,
+            "schedule" => match_schedule(ctx, command, guild, &keys, group).await,
+            _ => Err(SlashCommandError::UnknownSubCommand),
+        };
+    }
     if let CommandDataOptionValue::SubCommand(options) = &sub_cmd.value {
         match sub_cmd.name.as_str() {
             "add" => {
-                if let Some(option) = options.get(0) {
-                    if let CommandDataOptionValue::String(name) = &option.value {
-                        return commands::add(
-                            ctx,
-                            command,
-                            LOADED_REDIS_KEY,
-                            RAFFLE_REDIS_KEY,
-                            name,
-                        )
+                if let Some(CommandDataOptionValue::String(name)) = option(options, "name") {
+                    return commands::add(ctx, command, guild, &keys, name, locale)
                         .await
                         .map_err(|err| err.into());
-                    }
                 }
 
                 Err(SlashCommandError::MissingOption(
@@ -196,41 +791,157 @@ async fn match_subcommand(
                     "name".into(),
                 ))
             }
-            "clear" => commands::clear(ctx, command, LOADED_REDIS_KEY, RAFFLE_REDIS_KEY)
+            "clear" => commands::clear(ctx, command, guild, &keys, locale)
+                .await
+                .map_err(|err| err.into()),
+            "enter" => {
+                if let Some(CommandDataOptionValue::String(reference)) =
+                    option(options, "reference")
+                {
+                    return enter::enter(ctx, command, guild, &keys, reference)
+                        .await
+                        .map_err(|err| err.into());
+                }
+
+                Err(SlashCommandError::MissingOption(
+                    "enter".into(),
+                    "reference".into(),
+                ))
+            }
+            "export" => {
+                let format = match option(options, "format") {
+                    Some(CommandDataOptionValue::String(format)) => format
+                        .parse::<export::Format>()
+                        .map_err(|_| SlashCommandError::UnknownSubCommand)?,
+                    _ => {
+                        return Err(SlashCommandError::MissingOption(
+                            "export".into(),
+                            "format".into(),
+                        ))
+                    }
+                };
+                export::export(ctx, command, &keys, format, locale)
+                    .await
+                    .map_err(|err| err.into())
+            }
+            "import" => {
+                let attachment = match option(options, "file") {
+                    Some(CommandDataOptionValue::Attachment(id)) => {
+                        command.data.resolved.attachments.get(id)
+                    }
+                    _ => None,
+                }
+                .ok_or_else(|| SlashCommandError::MissingOption("import".into(), "file".into()))?;
+                export::import(ctx, command, guild, &keys, attachment, locale)
+                    .await
+                    .map_err(|err| err.into())
+            }
+            "list" => entries::list(ctx, command, &keys, locale)
+                .await
+                .map_err(|err| err.into()),
+            "odds" => {
+                let name = match option(options, "name") {
+                    Some(CommandDataOptionValue::String(name)) => Some(name.as_str()),
+                    _ => None,
+                };
+                history::odds(ctx, command, guild, &keys, name, locale)
+                    .await
+                    .map_err(|err| err.into())
+            }
+            "history" => history::history(ctx, command, guild, &keys, locale)
                 .await
                 .map_err(|err| err.into()),
+            "stats" => stats::show(ctx, command, guild, &keys, locale)
+                .await
+                .map_err(|err| err.into()),
+            "rehearse" => {
+                let seed = match option(options, "seed") {
+                    Some(CommandDataOptionValue::Integer(seed)) => Some(*seed as u64),
+                    _ => None,
+                };
+                let amount = match option(options, "amount") {
+                    Some(CommandDataOptionValue::Integer(amount)) => *amount as usize,
+                    _ => 1,
+                };
+                rehearse::rehearse_draw(ctx, command, guild, &keys, seed, amount, locale)
+                    .await
+                    .map_err(|err| err.into())
+            }
+            "snapshots" => snapshots::snapshots(ctx, command, guild, &keys, locale)
+                .await
+                .map_err(|err| err.into()),
+            "restore" => {
+                if let Some(CommandDataOptionValue::Integer(id)) = option(options, "id") {
+                    return snapshots::restore(ctx, command, guild, &keys, *id as u64, locale)
+                        .await
+                        .map_err(|err| err.into());
+                }
+
+                Err(SlashCommandError::MissingOption(
+                    "restore".into(),
+                    "id".into(),
+                ))
+            }
+            "remove" => {
+                if let Some(CommandDataOptionValue::String(name)) = option(options, "name") {
+                    return entries::remove(ctx, command, guild, &keys, name)
+                        .await
+                        .map_err(|err| err.into());
+                }
+
+                Err(SlashCommandError::MissingOption(
+                    "remove".into(),
+                    "name".into(),
+                ))
+            }
+            "search" => {
+                if let Some(CommandDataOptionValue::String(text)) = option(options, "text") {
+                    return entries::search(ctx, command, &keys, text, locale)
+                        .await
+                        .map_err(|err| err.into());
+                }
+
+                Err(SlashCommandError::MissingOption(
+                    "search".into(),
+                    "text".into(),
+                ))
+            }
             "load" => {
                 let load_params = commands::LoadParams {
-                    checkin_list_slug: &type_map_keys::CheckinListSlug::get(&ctx.data).await,
-                    loaded_redis_key: LOADED_REDIS_KEY,
-                    raffle_redis_key: RAFFLE_REDIS_KEY,
-                    ticket_slugs: [
-                        EARLY_BIRD_TICKET_SLUG,
-                        GENERAL_TICKET_SLUG,
-                        NO_SWAG_TICKET_SLUG,
-                        TICKET_SPOOFER_SLUG,
-                    ]
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect(),
+                    checkin_list_slug: &guild.checkin_list_slug,
+                    keys: &keys,
+                    rules: guild.rules(),
+                    admin: eligibility::AdminEvent::get(ctx, guild).await,
+                    exclusions: exclusions::exclusions(ctx, guild, &keys).await,
                 };
-                commands::load(ctx, command, load_params)
+                commands::load(ctx, command, load_params, locale)
                     .await
                     .map_err(|err| err.into())
             }
             "pick" => {
-                let amount: u64 = *options
-                    .get(0)
-                    .map(|option| match &option.value {
-                        CommandDataOptionValue::Integer(i) => Ok(i),
-                        _ => Err(SlashCommandError::UnknownSubCommand),
-                    })
-                    .unwrap_or(Ok(&1))? as u64;
-                commands::raffle(ctx, command, RAFFLE_REDIS_KEY, amount)
+                let amount = match option(options, "amount") {
+                    Some(CommandDataOptionValue::Integer(amount)) => *amount as u64,
+                    Some(_) => return Err(SlashCommandError::UnknownSubCommand),
+                    None => 1,
+                };
+                let prize = match option(options, "prize") {
+                    Some(CommandDataOptionValue::String(prize)) => Some(prize.as_str()),
+                    _ => None,
+                };
+                let dramatic = matches!(
+                    option(options, "dramatic"),
+                    Some(CommandDataOptionValue::Boolean(true))
+                );
+                let draw = commands::Draw {
+                    amount,
+                    prize,
+                    dramatic,
+                };
+                commands::raffle(ctx, command, guild, &keys, draw)
                     .await
                     .map_err(|err| err.into())
             }
-            "size" => commands::size(ctx, command, RAFFLE_REDIS_KEY)
+            "size" => commands::size(ctx, command, &keys, locale)
                 .await
                 .map_err(|err| err.into()),
             _ => Err(SlashCommandError::UnknownSubCommand),
@@ -240,38 +951,149 @@ async fn match_subcommand(
     }
 }
 
+/// Maps `/raffle exclude` Sub-Commands to function calls
+#[allow(clippy::result_large_err)]
+async fn match_exclude(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    group: &[CommandDataOption],
+    locale: Locale,
+) -> Result<(), SlashCommandError> {
+    let sub_cmd = group.first().ok_or(SlashCommandError::NoSubCommand)?;
+    let CommandDataOptionValue::SubCommand(options) = &sub_cmd.value else {
+        return Err(SlashCommandError::UnknownSubCommand);
+    };
+    let value = match option(options, "value") {
+        Some(CommandDataOptionValue::String(value)) => Some(value.as_str()),
+        _ => None,
+    };
+    match (sub_cmd.name.as_str(), value) {
+        ("add", Some(value)) => exclusions::add(ctx, command, guild, keys, value, locale)
+            .await
+            .map_err(|err| err.into()),
+        ("remove", Some(value)) => exclusions::remove(ctx, command, guild, keys, value, locale)
+            .await
+            .map_err(|err| err.into()),
+        ("add" | "remove", None) => Err(SlashCommandError::MissingOption(
+            format!("exclude {}", sub_cmd.name),
+            "value".into(),
+        )),
+        ("list", _) => exclusions::list(ctx, command, keys, locale)
+            .await
+            .map_err(|err| err.into()),
+        _ => Err(SlashCommandError::UnknownSubCommand),
+    }
+}
+
+/// Maps `/raffle schedule` Sub-Commands to function calls
+#[allow(clippy::result_large_err)]
+async fn match_schedule(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild: &GuildConfig,
+    keys: &RedisKeys,
+    group: &[CommandDataOption],
+) -> Result<(), SlashCommandError> {
+    let sub_cmd = group.first().ok_or(SlashCommandError::NoSubCommand)?;
+    let CommandDataOptionValue::SubCommand(options) = &sub_cmd.value else {
+        return Err(SlashCommandError::UnknownSubCommand);
+    };
+    match sub_cmd.name.as_str() {
+        "pick" => {
+            let Some(CommandDataOptionValue::String(at)) = option(options, "at") else {
+                return Err(SlashCommandError::MissingOption(
+                    "schedule pick".into(),
+                    "at".into(),
+                ));
+            };
+            let amount = match option(options, "amount") {
+                Some(CommandDataOptionValue::Integer(amount)) => *amount as u64,
+                Some(_) => return Err(SlashCommandError::UnknownSubCommand),
+                None => 1,
+            };
+            let prize = match option(options, "prize") {
+                Some(CommandDataOptionValue::String(prize)) => Some(prize.as_str()),
+                _ => None,
+            };
+            let repeat = match option(options, "repeat") {
+                Some(CommandDataOptionValue::Integer(minutes)) => Some(*minutes as u64),
+                _ => None,
+            };
+            let dramatic = matches!(
+                option(options, "dramatic"),
+                Some(CommandDataOptionValue::Boolean(true))
+            );
+            let draw = commands::Draw {
+                amount,
+                prize,
+                dramatic,
+            };
+            schedule::pick(ctx, command, guild, keys, at, draw, repeat)
+                .await
+                .map_err(|err| err.into())
+        }
+        "list" => schedule::list(ctx, command, guild, keys)
+            .await
+            .map_err(|err| err.into()),
+        "cancel" => {
+            let Some(CommandDataOptionValue::Integer(id)) = option(options, "id") else {
+                return Err(SlashCommandError::MissingOption(
+                    "schedule cancel".into(),
+                    "id".into(),
+                ));
+            };
+            schedule::cancel(ctx, command, guild, keys, *id as u64)
+                .await
+                .map_err(|err| err.into())
+        }
+        _ => Err(SlashCommandError::UnknownSubCommand),
+    }
+}
+
+/// Value of a sub-command's option by name
+fn option<'a>(options: &'a [CommandDataOption], name: &str) -> Option<&'a CommandDataOptionValue> {
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .map(|option| &option.value)
+}
+
 #[tokio::main]
 #[instrument]
 async fn main() {
     tracing_subscriber::fmt::init();
 
-    let checkin_list_slug =
-        env::var("CHECKIN_LIST_SLUG").expect("Expected env variable: CHECKIN_LIST_SLUG");
+    let config = Arc::new(Config::from_env().expect("Invalid configuration"));
     let redis_url = env::var("REDIS_TLS_URL").expect("Expected env variable: REDIS_TLS_URL");
     let discord_token = env::var("DISCORD_TOKEN").expect("Expected env variable: DISCORD_TOKEN");
-    let guild_id = GuildId::new(
-        env::var("DISCORD_GUILD_ID")
-            .expect("Expected env variable: DISCORD_GUILD_ID")
-            .parse()
-            .expect("DISCORD_GUILD_ID must be an integer"),
-    );
     let application_id = ApplicationId::new(
         env::var("DISCORD_APPLICATION_ID")
             .expect("Expected environment variable: DISCORD_APPLICATION_ID")
             .parse()
             .expect("application id is not a valid id"),
     );
-    let channel_id = ChannelId::new(
-        env::var("DISCORD_CHANNEL_ID")
-            .expect("Expected environment variable: DISCORD_CHANNEL_ID")
-            .parse()
-            .expect("channel id is not a valid id"),
-    );
-    let connection = redis_pool(&redis_url).await.unwrap();
+    let status_addr = SocketAddr::from((
+        [0, 0, 0, 0],
+        env::var("PORT")
+            .map(|port| port.parse().expect("PORT must be a port number"))
+            .unwrap_or(DEFAULT_STATUS_PORT),
+    ));
+    let connection = redis_pool(&redis_url, REDIS_POOL_MAX_SIZE).await.unwrap();
+    for guild in config.guilds.values() {
+        events::adopt_unscoped_keys(&connection, guild).await;
+    }
     let tito_client = tito::checkin::client::ClientBuilder::new()
         .expect("Could not build Tito HTTP Client")
         .build();
-    let rng = Arc::new(RwLock::new(rand::rngs::StdRng::from_entropy()));
+    let tito_admin_client = env::var("TITO_API_TOKEN").ok().map(|api_token| {
+        tito::admin::client::ClientBuilder::new(&api_token)
+            .expect("Could not build Tito Admin HTTP Client")
+            .build()
+    });
+    let rng = Arc::new(RwLock::new(DrawRng::os()));
+    let shutdown = Arc::new(shutdown::Shutdown::default());
 
     let http = Http::new(&discord_token);
     let bot_id = match http.get_current_user().await {
@@ -288,16 +1110,61 @@ async fn main() {
 
     {
         let mut data = client.data.write().await;
-        data.insert::<type_map_keys::CheckinListSlug>(checkin_list_slug);
-        data.insert::<type_map_keys::ChannelId>(channel_id);
-        data.insert::<type_map_keys::GuildId>(guild_id);
+        data.insert::<type_map_keys::Config>(config.clone());
         data.insert::<type_map_keys::UserId>(bot_id);
-        data.insert::<type_map_keys::RedisPool>(connection);
+        data.insert::<type_map_keys::RedisPool>(connection.clone());
         data.insert::<type_map_keys::TitoClient>(tito_client);
+        if let Some(tito_admin_client) = tito_admin_client {
+            data.insert::<type_map_keys::TitoAdminClient>(tito_admin_client);
+        }
         data.insert::<type_map_keys::Rng>(rng);
+        data.insert::<type_map_keys::Shutdown>(shutdown.clone());
+        data.insert::<type_map_keys::TicketCache>(Arc::default());
     }
 
+    let status = status::Status {
+        shard_manager: client.shard_manager.clone(),
+        redis_pool: connection,
+        redis_pool_max_size: REDIS_POOL_MAX_SIZE,
+        config,
+    };
+    let status_listener = tokio::spawn(async move {
+        info!("Serving /healthz and /metrics on {}", status_addr);
+        if let Err(err) = status::serve(status_addr, status).await {
+            error!("Status listener error: {:?}", err);
+        }
+    });
+
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        wait_for_signal().await;
+        info!("Shutting down, waiting for in-flight interactions");
+        let in_flight = shutdown.stop(SHUTDOWN_GRACE_PERIOD).await;
+        if in_flight > 0 {
+            error!("{} interactions still running at shutdown", in_flight);
+        }
+        shard_manager.shutdown_all().await;
+    });
+
     if let Err(err) = client.start().await {
         error!("Client error: {:?}", err);
     }
+
+    // the client and status listener hold the last Redis pool handles, the
+    // pool closes its connections once both are gone
+    status_listener.abort();
+    let _ = status_listener.await;
+    drop(client);
+    info!("Shut down");
+}
+
+/// Resolves on SIGTERM, sent by Heroku on every deploy and restart, or SIGINT
+async fn wait_for_signal() {
+    let mut terminate = signal(SignalKind::terminate()).expect("Cannot listen for SIGTERM");
+    let mut interrupt = signal(SignalKind::interrupt()).expect("Cannot listen for SIGINT");
+
+    tokio::select! {
+        _ = terminate.recv() => {},
+        _ = interrupt.recv() => {},
+    }
 }
@@ -0,0 +1,83 @@
+//! Prometheus metrics
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    Encoder, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+};
+use std::{future::Future, time::Instant};
+
+lazy_static! {
+    pub static ref COMMANDS: IntCounterVec = register_int_counter_vec!(
+        "casino_commands_total",
+        "Slash commands handled, by sub-command and result",
+        &["subcommand", "result"]
+    )
+    .unwrap();
+    pub static ref TITO_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "casino_tito_request_duration_seconds",
+        "Latency of Tito API requests",
+        &["endpoint"]
+    )
+    .unwrap();
+    pub static ref TITO_REQUEST_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "casino_tito_request_errors_total",
+        "Failed Tito API requests",
+        &["endpoint"]
+    )
+    .unwrap();
+    pub static ref RAFFLE_ENTRIES: IntGaugeVec = register_int_gauge_vec!(
+        "casino_raffle_entries",
+        "Entries left in the raffle",
+        &["guild"]
+    )
+    .unwrap();
+    pub static ref DRAWS: IntCounterVec =
+        register_int_counter_vec!("casino_draws_total", "Winners drawn", &["guild"]).unwrap();
+    pub static ref REDIS_CONNECTIONS: IntGaugeVec = register_int_gauge_vec!(
+        "casino_redis_connections",
+        "Redis pool connections, by state",
+        &["state"]
+    )
+    .unwrap();
+    pub static ref REDIS_POOL_MAX_SIZE: IntGauge = register_int_gauge!(
+        "casino_redis_pool_max_size",
+        "Maximum connections in the Redis pool"
+    )
+    .unwrap();
+}
+
+/// Time a Tito request and count it as an error if it fails
+pub async fn time_tito<T, E>(
+    endpoint: &str,
+    request: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let start = Instant::now();
+    let result = request.await;
+    TITO_REQUEST_DURATION
+        .with_label_values(&[endpoint])
+        .observe(start.elapsed().as_secs_f64());
+    if result.is_err() {
+        TITO_REQUEST_ERRORS.with_label_values(&[endpoint]).inc();
+    }
+
+    result
+}
+
+/// Record the state of a bb8 pool
+pub fn observe_pool(state: bb8::State, max_size: u32) {
+    REDIS_CONNECTIONS
+        .with_label_values(&["idle"])
+        .set(state.idle_connections.into());
+    REDIS_CONNECTIONS
+        .with_label_values(&["active"])
+        .set((state.connections - state.idle_connections).into());
+    REDIS_POOL_MAX_SIZE.set(max_size.into());
+}
+
+/// Render every registered metric in the Prometheus text format
+pub fn encode() -> Result<String, prometheus::Error> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&prometheus::gather(), &mut buffer)?;
+
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
@@ -0,0 +1,92 @@
+//! Tracks in-flight interactions so a restart can wait for them
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::Notify;
+
+#[derive(Debug, Default)]
+pub struct Shutdown {
+    stopping: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// Marks an interaction as in flight until dropped
+pub struct InFlight(Arc<Shutdown>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+impl Shutdown {
+    /// Start tracking an interaction. Returns `None` once shutdown has begun.
+    pub fn track(self: &Arc<Self>) -> Option<InFlight> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlight(self.clone());
+        if self.is_stopping() {
+            return None;
+        }
+
+        Some(guard)
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    /// Stop accepting work and wait up to `timeout` for in-flight work to
+    /// finish. Returns how many interactions were still running.
+    pub async fn stop(&self, timeout: Duration) -> usize {
+        self.stopping.store(true, Ordering::SeqCst);
+
+        let _ = tokio::time::timeout(timeout, async {
+            loop {
+                let idle = self.idle.notified();
+                if self.in_flight.load(Ordering::SeqCst) == 0 {
+                    break;
+                }
+                idle.await;
+            }
+        })
+        .await;
+
+        self.in_flight.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stop_waits_for_in_flight() {
+        let shutdown = Arc::new(Shutdown::default());
+        let guard = shutdown.track().unwrap();
+
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.stop(Duration::from_secs(5)).await }
+        });
+        tokio::task::yield_now().await;
+        assert!(shutdown.track().is_none());
+
+        drop(guard);
+        assert_eq!(waiter.await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn stop_gives_up_after_timeout() {
+        let shutdown = Arc::new(Shutdown::default());
+        let _guard = shutdown.track().unwrap();
+
+        assert_eq!(shutdown.stop(Duration::from_millis(10)).await, 1);
+    }
+}
@@ -0,0 +1,115 @@
+//! HTTP listener for `/healthz` and `/metrics`
+use crate::{config::Config, discord::commands::events, metrics};
+use bb8_redis::{redis, RedisConnectionManager};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, StatusCode,
+};
+use serenity::gateway::{ConnectionStage, ShardManager};
+use std::{convert::Infallible, net::SocketAddr, sync::Arc, time::Duration};
+use tracing::error;
+
+const REDIS_PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Everything the listener reports on
+#[derive(Clone)]
+pub struct Status {
+    pub shard_manager: Arc<ShardManager>,
+    pub redis_pool: bb8::Pool<RedisConnectionManager>,
+    pub redis_pool_max_size: u32,
+    pub config: Arc<Config>,
+}
+
+impl Status {
+    /// Whether every shard has an open gateway connection
+    async fn gateway_connected(&self) -> bool {
+        let runners = self.shard_manager.runners.lock().await;
+        !runners.is_empty()
+            && runners
+                .values()
+                .all(|runner| runner.stage == ConnectionStage::Connected)
+    }
+
+    async fn redis_reachable(&self) -> bool {
+        let ping = async {
+            let mut connection = self.redis_pool.get().await.ok()?;
+            redis::cmd("PING")
+                .query_async::<_, String>(&mut *connection)
+                .await
+                .ok()
+        };
+
+        matches!(
+            tokio::time::timeout(REDIS_PING_TIMEOUT, ping).await,
+            Ok(Some(_))
+        )
+    }
+
+    async fn healthz(&self) -> Response<Body> {
+        let (gateway, redis) = tokio::join!(self.gateway_connected(), self.redis_reachable());
+        let status = if gateway && redis {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+
+        let mut response = Response::new(Body::from(
+            serde_json::json!({ "gateway": gateway, "redis": redis }).to_string(),
+        ));
+        *response.status_mut() = status;
+        response
+    }
+
+    /// Refresh the gauges that are read from Redis, then render all metrics
+    async fn metrics(&self) -> Response<Body> {
+        metrics::observe_pool(self.redis_pool.state(), self.redis_pool_max_size);
+        if let Ok(mut connection) = self.redis_pool.get().await {
+            for guild in self.config.guilds.values() {
+                let event = events::current(&self.redis_pool, guild).await;
+                let size: redis::RedisResult<i64> = redis::cmd("LLEN")
+                    .arg(event.keys(guild).raffle)
+                    .query_async(&mut *connection)
+                    .await;
+                if let Ok(size) = size {
+                    metrics::RAFFLE_ENTRIES
+                        .with_label_values(&[&guild.guild_id.to_string()])
+                        .set(size);
+                }
+            }
+        }
+
+        match metrics::encode() {
+            Ok(body) => Response::new(Body::from(body)),
+            Err(err) => {
+                error!("Cannot encode metrics: {}", err);
+                let mut response = Response::new(Body::empty());
+                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                response
+            }
+        }
+    }
+
+    async fn route(self, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let response = match (request.method(), request.uri().path()) {
+            (&Method::GET, "/healthz") => self.healthz().await,
+            (&Method::GET, "/metrics") => self.metrics().await,
+            _ => {
+                let mut response = Response::new(Body::empty());
+                *response.status_mut() = StatusCode::NOT_FOUND;
+                response
+            }
+        };
+
+        Ok(response)
+    }
+}
+
+/// Serve `/healthz` and `/metrics` until the future is dropped
+pub async fn serve(addr: SocketAddr, status: Status) -> Result<(), hyper::Error> {
+    let make_service = make_service_fn(move |_| {
+        let status = status.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |request| status.clone().route(request))) }
+    });
+
+    hyper::Server::bind(&addr).serve(make_service).await
+}
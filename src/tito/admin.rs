@@ -1,3 +1,4 @@
+pub mod answer;
 pub mod client;
 pub mod meta;
 pub mod ticket;
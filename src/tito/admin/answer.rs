@@ -0,0 +1,34 @@
+use crate::tito::admin::meta::Meta;
+use chrono::{offset::Utc, DateTime};
+use serde::Deserialize;
+
+/// An attendee's response to a registration question
+#[derive(Debug, Deserialize)]
+pub struct Answer {
+    pub _type: String,
+    pub id: u32,
+    pub question_id: u32,
+    pub ticket_id: u32,
+    pub response: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Answers {
+    pub answers: Vec<Answer>,
+    pub meta: Meta,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_deserialize() {
+        let answers: Result<Answers, _> =
+            serde_json::from_str(include_str!("../../../fixtures/admin/answers.json"));
+
+        assert!(answers.is_ok());
+    }
+}
@@ -1,6 +1,10 @@
+pub mod answers_handler;
+pub mod ticket_handler;
 pub mod tickets_handler;
 
+use answers_handler::AnswersHandler;
 use reqwest::header;
+use ticket_handler::TicketHandler;
 use tickets_handler::TicketsHandler;
 
 const TITO_API_BASE: &str = "https://api.tito.io/v3/";
@@ -56,7 +60,7 @@ impl ClientBuilder {
 }
 
 /// Tito API Client
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Client {
     client: reqwest::Client,
     base_url: String,
@@ -66,4 +70,24 @@ impl<'a> Client {
     pub fn tickets(&'a self, account_slug: &str, event_slug: &str) -> TicketsHandler<'a> {
         TicketsHandler::new(self, account_slug, event_slug)
     }
+
+    /// A single ticket, by its slug
+    pub fn ticket(
+        &'a self,
+        account_slug: &str,
+        event_slug: &str,
+        ticket_slug: &str,
+    ) -> TicketHandler<'a> {
+        TicketHandler::new(self, account_slug, event_slug, ticket_slug)
+    }
+
+    /// Answers to a registration question, by the question's slug
+    pub fn answers(
+        &'a self,
+        account_slug: &str,
+        event_slug: &str,
+        question_slug: &str,
+    ) -> AnswersHandler<'a> {
+        AnswersHandler::new(self, account_slug, event_slug, question_slug)
+    }
 }
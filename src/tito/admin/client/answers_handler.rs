@@ -0,0 +1,52 @@
+use crate::tito::admin::{
+    answer::{Answer, Answers},
+    client::Client,
+};
+
+/// Client to the answers of one question in Tito's Questions API
+pub struct AnswersHandler<'client> {
+    client: &'client Client,
+    account: String,
+    event: String,
+    question: String,
+}
+
+impl<'client> AnswersHandler<'client> {
+    pub(crate) fn new(
+        client: &'client Client,
+        account: impl Into<String>,
+        event: impl Into<String>,
+        question: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            account: account.into(),
+            event: event.into(),
+            question: question.into(),
+        }
+    }
+
+    /// Execute the request to fetch all answers
+    pub async fn send(&self) -> Result<Vec<Answer>, reqwest::Error> {
+        let mut next_page = Some(1);
+        let mut answers: Vec<Answer> = Vec::new();
+        while let Some(page) = next_page {
+            let mut response = self.build(page).send().await?.json::<Answers>().await?;
+
+            next_page = response.meta.next_page;
+            answers.append(&mut response.answers);
+        }
+
+        Ok(answers)
+    }
+
+    fn build(&self, page: u32) -> reqwest::RequestBuilder {
+        self.client
+            .client
+            .get(format!(
+                "{}/{}/{}/questions/{}/answers",
+                self.client.base_url, self.account, self.event, self.question
+            ))
+            .query(&[("page", page)])
+    }
+}
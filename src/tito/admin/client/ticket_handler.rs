@@ -0,0 +1,45 @@
+use crate::tito::admin::{client::Client, ticket::Ticket};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct TicketResponse {
+    ticket: Ticket,
+}
+
+/// Client to a single ticket of Tito's Tickets API
+pub struct TicketHandler<'client> {
+    client: &'client Client,
+    account: String,
+    event: String,
+    ticket: String,
+}
+
+impl<'client> TicketHandler<'client> {
+    pub(crate) fn new(
+        client: &'client Client,
+        account: impl Into<String>,
+        event: impl Into<String>,
+        ticket: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            account: account.into(),
+            event: event.into(),
+            ticket: ticket.into(),
+        }
+    }
+
+    /// Execute the request to fetch the ticket
+    pub async fn send(&self) -> Result<Ticket, reqwest::Error> {
+        let response = self.build().send().await?.json::<TicketResponse>().await?;
+
+        Ok(response.ticket)
+    }
+
+    fn build(&self) -> reqwest::RequestBuilder {
+        self.client.client.get(format!(
+            "{}/{}/{}/tickets/{}",
+            self.client.base_url, self.account, self.event, self.ticket
+        ))
+    }
+}
@@ -24,7 +24,7 @@ impl<'client> CheckinListsHandler<'client> {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Debug, Deserialize)]
 pub struct Ticket {
     pub id: u32,
     pub slug: String,
@@ -41,6 +41,16 @@ pub struct Ticket {
     pub updated_at: DateTime<Utc>,
 }
 
+impl Ticket {
+    /// "First Last", if the attendee filled in both
+    pub fn full_name(&self) -> Option<String> {
+        match (&self.first_name, &self.last_name) {
+            (Some(first_name), Some(last_name)) => Some(format!("{first_name} {last_name}")),
+            _ => None,
+        }
+    }
+}
+
 pub struct TicketsHandler<'a> {
     checkin_lists_handler: &'a CheckinListsHandler<'a>,
 }
@@ -0,0 +1,55 @@
+//! Short lived copy of check-in list tickets, so autocomplete doesn't hit
+//! Tito on every keystroke
+use crate::{
+    metrics,
+    tito::checkin::client::{checkin_lists_handler::Ticket, Client},
+};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+
+const TICKET_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// When the tickets were fetched, and the tickets
+type CachedTickets = (Instant, Arc<Vec<Ticket>>);
+
+#[derive(Default)]
+pub struct TicketCache {
+    lists: RwLock<HashMap<String, CachedTickets>>,
+}
+
+impl TicketCache {
+    /// Tickets of a check-in list, fetched again once the copy is stale
+    pub async fn tickets(
+        &self,
+        tito_client: &Client,
+        checkin_list_slug: &str,
+    ) -> Result<Arc<Vec<Ticket>>, reqwest::Error> {
+        if let Some((fetched_at, tickets)) = self.lists.read().await.get(checkin_list_slug) {
+            if fetched_at.elapsed() < TICKET_CACHE_TTL {
+                return Ok(tickets.clone());
+            }
+        }
+
+        let tickets = metrics::time_tito(
+            "tickets",
+            tito_client.check_ins(checkin_list_slug).tickets().send(),
+        )
+        .await?;
+        Ok(self.store(checkin_list_slug, tickets).await)
+    }
+
+    /// Replace the cached copy with freshly fetched tickets
+    pub async fn store(&self, checkin_list_slug: &str, tickets: Vec<Ticket>) -> Arc<Vec<Ticket>> {
+        let tickets = Arc::new(tickets);
+        self.lists.write().await.insert(
+            checkin_list_slug.to_string(),
+            (Instant::now(), tickets.clone()),
+        );
+
+        tickets
+    }
+}